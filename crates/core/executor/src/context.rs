@@ -6,6 +6,7 @@ use hashbrown::HashMap;
 use crate::{
     hook::{hookify, BoxedHook, HookEnv, HookRegistry},
     subproof::SubproofVerifier,
+    FallOffPolicy,
 };
 
 /// Context to run a program inside SP1.
@@ -21,6 +22,9 @@ pub struct SP1Context<'a> {
 
     /// The maximum number of cpu cycles to use for execution.
     pub max_cycles: Option<u64>,
+
+    /// What to do when the program counter leaves the program without halting.
+    pub fall_off_policy: FallOffPolicy,
 }
 
 /// A builder for [`SP1Context`].
@@ -30,6 +34,7 @@ pub struct SP1ContextBuilder<'a> {
     hook_registry_entries: Vec<(u32, BoxedHook<'a>)>,
     subproof_verifier: Option<Arc<dyn SubproofVerifier + 'a>>,
     max_cycles: Option<u64>,
+    fall_off_policy: FallOffPolicy,
 }
 
 impl<'a> SP1Context<'a> {
@@ -68,7 +73,8 @@ impl<'a> SP1ContextBuilder<'a> {
             });
         let subproof_verifier = take(&mut self.subproof_verifier);
         let cycle_limit = take(&mut self.max_cycles);
-        SP1Context { hook_registry, subproof_verifier, max_cycles: cycle_limit }
+        let fall_off_policy = take(&mut self.fall_off_policy);
+        SP1Context { hook_registry, subproof_verifier, max_cycles: cycle_limit, fall_off_policy }
     }
 
     /// Add a runtime [Hook](super::Hook) into the context.
@@ -110,21 +116,32 @@ impl<'a> SP1ContextBuilder<'a> {
         self.max_cycles = Some(max_cycles);
         self
     }
+
+    /// Set what to do when the program counter leaves the program without halting.
+    pub fn fall_off_policy(&mut self, fall_off_policy: FallOffPolicy) -> &mut Self {
+        self.fall_off_policy = fall_off_policy;
+        self
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
 
-    use crate::{subproof::DefaultSubproofVerifier, SP1Context};
+    use crate::{subproof::DefaultSubproofVerifier, FallOffPolicy, SP1Context};
 
     #[test]
     fn defaults() {
-        let SP1Context { hook_registry, subproof_verifier, max_cycles: cycle_limit } =
-            SP1Context::builder().build();
+        let SP1Context {
+            hook_registry,
+            subproof_verifier,
+            max_cycles: cycle_limit,
+            fall_off_policy,
+        } = SP1Context::builder().build();
         assert!(hook_registry.is_none());
         assert!(subproof_verifier.is_none());
         assert!(cycle_limit.is_none());
+        assert_eq!(fall_off_policy, FallOffPolicy::Error);
     }
 
     #[test]
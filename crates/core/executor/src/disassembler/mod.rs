@@ -5,3 +5,4 @@ mod rrs;
 
 pub(crate) use elf::*;
 pub(crate) use rrs::*;
+pub use rrs::{decode, decode_words_par, DecodeError};
@@ -1,9 +1,13 @@
+#[cfg(feature = "rayon")]
+use p3_maybe_rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
 use rrs_lib::{
     instruction_formats::{BType, IType, ITypeCSR, ITypeShamt, JType, RType, SType, UType},
     process_instruction, InstructionProcessor,
 };
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
-use crate::{Instruction, Opcode, Register};
+use crate::{sign_extend, Instruction, Opcode, Register};
 
 impl Instruction {
     /// Create a new [`Instruction`] from an R-type instruction.
@@ -22,7 +26,14 @@ impl Instruction {
     /// Create a new [`Instruction`] from an I-type instruction.
     #[must_use]
     pub const fn from_i_type(opcode: Opcode, dec_insn: &IType) -> Self {
-        Self::new(opcode, dec_insn.rd as u32, dec_insn.rs1 as u32, dec_insn.imm as u32, false, true)
+        Self::new(
+            opcode,
+            dec_insn.rd as u32,
+            dec_insn.rs1 as u32,
+            sign_extend(dec_insn.imm as u32, 12),
+            false,
+            true,
+        )
     }
 
     /// Create a new [`Instruction`] from an I-type instruction with a shamt.
@@ -38,7 +49,7 @@ impl Instruction {
             opcode,
             dec_insn.rs2 as u32,
             dec_insn.rs1 as u32,
-            dec_insn.imm as u32,
+            sign_extend(dec_insn.imm as u32, 12),
             false,
             true,
         )
@@ -51,7 +62,7 @@ impl Instruction {
             opcode,
             dec_insn.rs1 as u32,
             dec_insn.rs2 as u32,
-            dec_insn.imm as u32,
+            sign_extend(dec_insn.imm as u32, 13),
             false,
             true,
         )
@@ -263,7 +274,14 @@ impl InstructionProcessor for InstructionTranspiler {
     }
 
     fn process_jal(&mut self, dec_insn: JType) -> Self::InstructionResult {
-        Instruction::new(Opcode::JAL, dec_insn.rd as u32, dec_insn.imm as u32, 0, true, true)
+        Instruction::new(
+            Opcode::JAL,
+            dec_insn.rd as u32,
+            sign_extend(dec_insn.imm as u32, 21),
+            0,
+            true,
+            true,
+        )
     }
 
     fn process_jalr(&mut self, dec_insn: IType) -> Self::InstructionResult {
@@ -271,7 +289,7 @@ impl InstructionProcessor for InstructionTranspiler {
             Opcode::JALR,
             dec_insn.rd as u32,
             dec_insn.rs1 as u32,
-            dec_insn.imm as u32,
+            sign_extend(dec_insn.imm as u32, 12),
             false,
             true,
         )
@@ -396,3 +414,114 @@ pub(crate) fn transpile(instructions_u32: &[u32]) -> Vec<Instruction> {
     }
     instructions
 }
+
+/// An error returned when a word can't be decoded into an [`Instruction`].
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[error("failed to decode word {word:#010x} at index {index}")]
+pub struct DecodeError {
+    /// The index of the word.
+    pub index: usize,
+    /// The word that failed to decode.
+    pub word: u32,
+}
+
+/// Decode a single 32-bit encoded RV32IM instruction into an [`Instruction`].
+///
+/// The operands are laid out the way [`Instruction::r_type`], [`Instruction::i_type`], etc. read
+/// them back, with the immediates sign-extended. The `index` of the returned error is always 0.
+///
+/// # Errors
+///
+/// This function will return a [`DecodeError`] if the word is not a valid RV32IM instruction.
+pub fn decode(word: u32) -> Result<Instruction, DecodeError> {
+    process_instruction(&mut InstructionTranspiler, word).ok_or(DecodeError { index: 0, word })
+}
+
+/// Transpile the [`Instruction`]s from the 32-bit encoded instructions in parallel.
+///
+/// The words are only decoded in parallel with the `rayon` feature, and one after the other
+/// otherwise.
+///
+/// # Errors
+///
+/// This function will return a [`DecodeError`] for the first word that cannot be decoded.
+pub fn decode_words_par(words: &[u32]) -> Result<Vec<Instruction>, DecodeError> {
+    #[cfg(feature = "rayon")]
+    let words_iter = words.par_iter();
+    #[cfg(not(feature = "rayon"))]
+    let words_iter = words.iter();
+    let instructions = words_iter
+        .map(|word| process_instruction(&mut InstructionTranspiler, *word))
+        .collect::<Vec<_>>();
+    instructions
+        .into_iter()
+        .zip(words)
+        .enumerate()
+        .map(|(index, (instruction, word))| instruction.ok_or(DecodeError { index, word: *word }))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, decode_words_par, transpile, DecodeError};
+    use crate::{Instruction, Opcode, Register};
+
+    #[test]
+    fn test_decode_words_par() {
+        let words = [
+            // addi x29, x0, 5
+            0x0050_0e93,
+            // addi x30, x0, 37
+            0x0250_0f13,
+            // add x31, x30, x29
+            0x01df_0fb3,
+        ]
+        .repeat(1000);
+
+        let fields = |instructions: Vec<_>| {
+            instructions
+                .into_iter()
+                .map(|i: super::Instruction| (i.opcode, i.op_a, i.op_b, i.op_c, i.imm_b, i.imm_c))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(fields(decode_words_par(&words).unwrap()), fields(transpile(&words)));
+
+        let mut words = words;
+        words[1234] = 0xffff_ffff;
+        words[2345] = 0xffff_ffff;
+        assert_eq!(decode_words_par(&words), Err(DecodeError { index: 1234, word: 0xffff_ffff }));
+    }
+
+    #[test]
+    fn test_decode() {
+        let cases = [
+            // addi x1, x2, -1
+            (0xfff1_0093, Instruction::new(Opcode::ADD, 1, 2, -1i32 as u32, false, true)),
+            // mul x5, x6, x7
+            (0x0273_02b3, Instruction::new(Opcode::MUL, 5, 6, 7, false, false)),
+            // sw x5, -4(x2)
+            (0xfe51_2e23, Instruction::new(Opcode::SW, 5, 2, -4i32 as u32, false, true)),
+            // beq x1, x2, -8
+            (0xfe20_8ce3, Instruction::new(Opcode::BEQ, 1, 2, -8i32 as u32, false, true)),
+            // lui x3, 0x12345
+            (0x1234_51b7, Instruction::new(Opcode::ADD, 3, 0, 0x1234_5000, true, true)),
+            // jal x1, -16
+            (0xff1f_f0ef, Instruction::new(Opcode::JAL, 1, -16i32 as u32, 0, true, true)),
+            // jalr x1, -2048(x2)
+            (0x8001_00e7, Instruction::new(Opcode::JALR, 1, 2, -2048i32 as u32, false, true)),
+        ];
+        for (word, expected) in cases {
+            let instruction = decode(word).unwrap();
+            assert_eq!(
+                (instruction.opcode, instruction.op_a, instruction.op_b, instruction.op_c),
+                (expected.opcode, expected.op_a, expected.op_b, expected.op_c),
+                "{word:#010x}"
+            );
+            assert_eq!((instruction.imm_b, instruction.imm_c), (expected.imm_b, expected.imm_c));
+        }
+
+        let addi = decode(0xfff1_0093).unwrap();
+        assert_eq!(addi.i_type(), (Register::X1, Register::X2, u32::MAX));
+        assert_eq!(decode(0xffff_ffff).unwrap_err(), DecodeError { index: 0, word: 0xffff_ffff });
+    }
+}
@@ -46,4 +46,44 @@ impl AluEvent {
             sub_lookups: create_alu_lookups(),
         }
     }
+
+    /// Recomputes the result of the operation from the opcode and the operands `b` and `c`, with
+    /// the same semantics as the executor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the opcode is not an ALU opcode.
+    #[must_use]
+    pub fn expected_a(&self) -> u32 {
+        let (b, c) = (self.b, self.c);
+        match self.opcode {
+            Opcode::ADD => b.wrapping_add(c),
+            Opcode::SUB => b.wrapping_sub(c),
+            Opcode::XOR => b ^ c,
+            Opcode::OR => b | c,
+            Opcode::AND => b & c,
+            Opcode::SLL => b.wrapping_shl(c),
+            Opcode::SRL => b.wrapping_shr(c),
+            Opcode::SRA => (b as i32).wrapping_shr(c) as u32,
+            Opcode::SLT => u32::from((b as i32) < (c as i32)),
+            Opcode::SLTU => u32::from(b < c),
+            Opcode::MUL => b.wrapping_mul(c),
+            Opcode::MULH => (((b as i32) as i64).wrapping_mul((c as i32) as i64) >> 32) as u32,
+            Opcode::MULHU => ((b as u64).wrapping_mul(c as u64) >> 32) as u32,
+            Opcode::MULHSU => (((b as i32) as i64).wrapping_mul(c as i64) >> 32) as u32,
+            Opcode::DIV | Opcode::DIVU if c == 0 => u32::MAX,
+            Opcode::REM | Opcode::REMU if c == 0 => b,
+            Opcode::DIV => (b as i32).wrapping_div(c as i32) as u32,
+            Opcode::DIVU => b / c,
+            Opcode::REM => (b as i32).wrapping_rem(c as i32) as u32,
+            Opcode::REMU => b % c,
+            opcode => panic!("{opcode} is not an ALU opcode"),
+        }
+    }
+
+    /// Returns whether the event is a signed comparison (i.e. SLT as opposed to SLTU).
+    #[must_use]
+    pub fn is_signed_comparison(&self) -> bool {
+        self.opcode == Opcode::SLT
+    }
 }
@@ -1,4 +1,6 @@
 use serde::{Deserialize, Serialize};
+use strum_macros::EnumIter;
+use thiserror::Error;
 
 /// Memory Record.
 ///
@@ -33,6 +35,18 @@ pub enum MemoryAccessPosition {
     A = 3,
 }
 
+/// Memory Operation.
+///
+/// This enum represents the kind of operation performed on a memory address. It can be converted
+/// to and from a `u8` so that memory events can be serialized compactly.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, EnumIter, Serialize, Deserialize)]
+pub enum MemoryOp {
+    /// Read.
+    Read = 0,
+    /// Write.
+    Write = 1,
+}
+
 /// Memory Read Record.
 ///
 /// This object encapsulates the information needed to prove a memory read operation. This
@@ -135,7 +149,43 @@ impl MemoryWriteRecord {
     }
 }
 
+/// A byte that doesn't encode a [`MemoryOp`].
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("invalid memory op {0}")]
+pub struct InvalidMemoryOp(pub u8);
+
+impl TryFrom<u8> for MemoryOp {
+    type Error = InvalidMemoryOp;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(MemoryOp::Read),
+            1 => Ok(MemoryOp::Write),
+            _ => Err(InvalidMemoryOp(value)),
+        }
+    }
+}
+
+impl MemoryOp {
+    /// Convert the [`MemoryOp`] to a u8.
+    #[must_use]
+    pub const fn as_u8(self) -> u8 {
+        self as u8
+    }
+}
+
 impl MemoryRecordEnum {
+    /// Returns the value of the memory before the access.
+    ///
+    /// Reads don't modify memory, so this is the same as [`Self::value`] for them.
+    #[must_use]
+    pub const fn prev_value(&self) -> u32 {
+        match self {
+            MemoryRecordEnum::Read(record) => record.value,
+            MemoryRecordEnum::Write(record) => record.prev_value,
+        }
+    }
+
     /// Returns the value of the memory record.
     #[must_use]
     pub const fn value(&self) -> u32 {
@@ -144,6 +194,15 @@ impl MemoryRecordEnum {
             MemoryRecordEnum::Write(record) => record.value,
         }
     }
+
+    /// Returns the operation of the memory record.
+    #[must_use]
+    pub const fn op(&self) -> MemoryOp {
+        match self {
+            MemoryRecordEnum::Read(_) => MemoryOp::Read,
+            MemoryRecordEnum::Write(_) => MemoryOp::Write,
+        }
+    }
 }
 
 impl MemoryInitializeFinalizeEvent {
@@ -177,3 +236,18 @@ impl From<MemoryWriteRecord> for MemoryRecordEnum {
         MemoryRecordEnum::Write(write_record)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use strum::IntoEnumIterator;
+
+    use super::{InvalidMemoryOp, MemoryOp};
+
+    #[test]
+    fn test_memory_op_round_trip() {
+        for op in MemoryOp::iter() {
+            assert_eq!(MemoryOp::try_from(op.as_u8()), Ok(op));
+        }
+        assert_eq!(MemoryOp::try_from(2), Err(InvalidMemoryOp(2)));
+    }
+}
@@ -1,5 +1,7 @@
 use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
     fs::File,
+    hash::{DefaultHasher, Hash, Hasher},
     io::{BufWriter, Write},
     sync::Arc,
 };
@@ -7,21 +9,27 @@ use std::{
 use hashbrown::{hash_map::Entry, HashMap, HashSet};
 use nohash_hasher::BuildNoHashHasher;
 use serde::{Deserialize, Serialize};
-use sp1_stark::SP1CoreOpts;
+use sp1_stark::{air::PublicValues, SP1CoreOpts, SplitOpts};
 use thiserror::Error;
 
 use crate::{
+    as_signed,
     context::SP1Context,
+    disassembler::{decode_words_par, DecodeError},
     events::{
         create_alu_lookup_id, create_alu_lookups, AluEvent, CpuEvent, MemoryAccessPosition,
-        MemoryInitializeFinalizeEvent, MemoryReadRecord, MemoryRecord, MemoryWriteRecord,
+        MemoryInitializeFinalizeEvent, MemoryReadRecord, MemoryRecord, MemoryRecordEnum,
+        MemoryWriteRecord,
     },
     hook::{HookEnv, HookRegistry},
-    record::{ExecutionRecord, MemoryAccessRecord},
+    record::{ChipKind, ExecutionRecord, MemoryAccessRecord},
     report::ExecutionReport,
-    state::{ExecutionState, ForkState},
+    spill::{MemorySpill, PageStore},
+    state::{ExecutionState, ForkState, UndoState},
     subproof::{DefaultSubproofVerifier, SubproofVerifier},
-    syscalls::{default_syscall_map, Syscall, SyscallCode, SyscallContext},
+    syscalls::{
+        default_syscall_map, memory_syscall_map, Syscall, SyscallAbi, SyscallCode, SyscallContext,
+    },
     Instruction, Opcode, Program, Register,
 };
 
@@ -100,8 +108,74 @@ pub struct Executor<'a> {
     /// Memory addresses that were touched in this batch of shards. Used to minimize the size of
     /// checkpoints.
     pub touched_memory: HashSet<u32, BuildNoHashHasher<u32>>,
+
+    /// What to do when the program counter leaves the program without halting.
+    pub fall_off_policy: FallOffPolicy,
+
+    /// What loads of memory that was never written return.
+    pub uninit_pattern: UninitPattern,
+
+    /// The program counter of the `EBREAK` that stopped the execution, if any.
+    pub break_pc: Option<u32>,
+
+    /// Whether to fail when the stack pointer (`x2`) is set above [`STACK_TOP`]. This catches stack
+    /// overflows, which wrap `sp` below zero, as well as `sp` growing past the top of the stack.
+    pub check_stack: bool,
+
+    /// Whether to allow misaligned `LH`, `LHU`, `LW`, `SH` and `SW` instead of failing with
+    /// [`ExecutionError::MisalignedMemoryAccess`]. Accesses spanning two words read or write both
+    /// of them, but only the first word would be part of the cpu event, so such executions can't
+    /// be proven: they are only allowed while no events are emitted, as with
+    /// [`Executor::run_untraced`], and fail with [`ExecutionError::UnprovableMisalignedAccess`]
+    /// otherwise.
+    pub allow_misaligned: bool,
+
+    /// The memory ranges, as `(base, len)` pairs, that stores are not allowed to write to.
+    pub readonly_regions: Vec<(u32, u32)>,
+
+    /// The maximum number of instructions executed with [`Executor::step`] that can be reverted
+    /// with [`Executor::undo`], dropping the oldest first. Stepping doesn't save the state needed
+    /// to revert the instructions when zero, which is the default.
+    pub undo_depth: usize,
+
+    /// The undo entries of the instructions executed with [`Executor::step`], most recent last.
+    pub(crate) undo_log: VecDeque<UndoState>,
+
+    /// The undo entry of the step currently being executed, if any.
+    pub(crate) undo_step: Option<UndoState>,
+
+    /// The maximum number of instructions kept in [`Executor::trace_ring`].
+    pub trace_ring_capacity: usize,
+
+    /// The most recently executed instructions along with their program counters, oldest first.
+    pub trace_ring: VecDeque<(u32, Instruction)>,
+
+    /// The callbacks to invoke when an address is written, keyed by address.
+    pub watchpoints: HashMap<u32, Watchpoint<'a>>,
+
+    /// The thresholds past which the precompile events are deferred, and the sink receiving them.
+    pub auto_defer: Option<(SplitOpts, DeferSink<'a>)>,
+
+    /// The number of recent states kept in [`Executor::loop_states`] to detect infinite loops.
+    pub loop_window: usize,
+
+    /// The hashes of the program counter and registers of the most recent cycles, oldest first.
+    pub loop_states: VecDeque<u64>,
+
+    /// The bound on the resident memory, if cold pages are spilled to a backing store.
+    pub memory_spill: Option<MemorySpill>,
 }
 
+/// A callback invoked with the runtime and the new value whenever a watched address is written.
+///
+/// See [`Executor::add_watchpoint`].
+pub type Watchpoint<'a> = Box<dyn FnMut(&Executor<'a>, u32) + Send + 'a>;
+
+/// A callback receiving the records of precompile events deferred automatically.
+///
+/// See [`Executor::with_auto_defer`].
+pub type DeferSink<'a> = Box<dyn FnMut(ExecutionRecord) + Send + 'a>;
+
 /// Errors that the [``Executor``] can throw.
 #[derive(Error, Debug, Serialize, Deserialize)]
 pub enum ExecutionError {
@@ -110,14 +184,65 @@ pub enum ExecutionError {
     HaltWithNonZeroExitCode(u32),
 
     /// The execution failed with an invalid memory access.
+    ///
+    /// The executor reports misaligned accesses with [`ExecutionError::MisalignedMemoryAccess`],
+    /// which also locates the faulting instruction.
     #[error("invalid memory access for opcode {0} and address {1}")]
     InvalidMemoryAccess(Opcode, u32),
 
+    /// The execution failed with a misaligned memory access.
+    #[error(
+        "invalid memory access for opcode {opcode} and address {addr} at pc {pc:#x} (clk {clk})"
+    )]
+    MisalignedMemoryAccess {
+        /// The opcode of the faulting instruction.
+        opcode: Opcode,
+        /// The address that was accessed.
+        addr: u32,
+        /// The program counter of the faulting instruction.
+        pc: u32,
+        /// The clock of the faulting instruction.
+        clk: u32,
+    },
+
+    /// A misaligned memory access was allowed with [`Executor::allow_misaligned`], but the
+    /// execution emits events for proving, which can't represent it.
+    #[error(
+        "misaligned memory access for opcode {opcode} and address {addr} can't be proven at pc \
+         {pc:#x} (clk {clk})"
+    )]
+    UnprovableMisalignedAccess {
+        /// The opcode of the faulting instruction.
+        opcode: Opcode,
+        /// The address that was accessed.
+        addr: u32,
+        /// The program counter of the faulting instruction.
+        pc: u32,
+        /// The clock of the faulting instruction.
+        clk: u32,
+    },
+
     /// The execution failed with an unimplemented syscall.
+    ///
+    /// The executor reports unknown syscalls with [`ExecutionError::UnknownSyscall`], which also
+    /// locates the faulting instruction.
     #[error("unimplemented syscall {0}")]
     UnsupportedSyscall(u32),
 
+    /// The execution failed with a syscall that is unknown or not registered.
+    #[error("unimplemented syscall {syscall_id} at pc {pc:#x} (clk {clk})")]
+    UnknownSyscall {
+        /// The id of the syscall.
+        syscall_id: u32,
+        /// The program counter of the faulting instruction.
+        pc: u32,
+        /// The clock of the faulting instruction.
+        clk: u32,
+    },
+
     /// The execution failed with a breakpoint.
+    ///
+    /// The executor stops cleanly at an `EBREAK` instead, see [`Executor::break_pc`].
     #[error("breakpoint encountered")]
     Breakpoint(),
 
@@ -125,9 +250,306 @@ pub enum ExecutionError {
     #[error("exceeded cycle limit of {0}")]
     ExceededCycleLimit(u64),
 
+    /// The execution reached the same program counter with the same registers twice within the
+    /// window set with [`Executor::with_loop_detection`].
+    #[error("infinite loop detected at pc {pc:#x} (clk {clk})")]
+    InfiniteLoopDetected {
+        /// The program counter at which the state repeated.
+        pc: u32,
+        /// The clock at which the state repeated.
+        clk: u32,
+    },
+
     /// The execution failed with an unimplemented feature.
+    ///
+    /// The executor reports `UNIMP` with [`ExecutionError::UnimplementedInstruction`], which also
+    /// locates the faulting instruction.
     #[error("got unimplemented as opcode")]
     Unimplemented(),
+
+    /// The execution reached an `UNIMP` instruction.
+    #[error("got unimplemented as opcode at pc {pc:#x} (clk {clk})")]
+    UnimplementedInstruction {
+        /// The program counter of the faulting instruction.
+        pc: u32,
+        /// The clock of the faulting instruction.
+        clk: u32,
+    },
+
+    /// The execution failed with a branch or jump to a misaligned target.
+    #[error("misaligned branch target {target:#x} at pc {pc:#x} (clk {clk})")]
+    MisalignedPc {
+        /// The target of the branch or jump.
+        target: u32,
+        /// The program counter of the faulting instruction.
+        pc: u32,
+        /// The clock of the faulting instruction.
+        clk: u32,
+    },
+
+    /// The entrypoint is not aligned or outside of the program's code.
+    #[error("invalid entrypoint {0:#x}")]
+    InvalidEntrypoint(u32),
+
+    /// The program could not be decoded.
+    #[error(transparent)]
+    Decode(#[from] DecodeError),
+
+    /// The store of the memory spilled with [`Executor::with_memory_spill`] failed.
+    #[error("failed to spill memory: {0}")]
+    MemorySpill(String),
+
+    /// An address or length passed to a memory syscall is not word-aligned.
+    #[error("{syscall} argument {arg:#x} is not word-aligned at pc {pc:#x} (clk {clk})")]
+    MisalignedMemorySyscall {
+        /// The memory syscall.
+        syscall: SyscallCode,
+        /// The misaligned argument.
+        arg: u32,
+        /// The program counter of the faulting instruction.
+        pc: u32,
+        /// The clock of the faulting instruction.
+        clk: u32,
+    },
+
+    /// The source and destination ranges of a `MEMCPY` syscall overlap.
+    #[error(
+        "memcpy of {len} bytes from {src:#x} to {dst:#x} has overlapping ranges at pc {pc:#x} \
+         (clk {clk})"
+    )]
+    OverlappingMemcpy {
+        /// The start of the destination range.
+        dst: u32,
+        /// The start of the source range.
+        src: u32,
+        /// The number of bytes to copy.
+        len: u32,
+        /// The program counter of the faulting instruction.
+        pc: u32,
+        /// The clock of the faulting instruction.
+        clk: u32,
+    },
+
+    /// The execution failed with a store to a read-only memory region.
+    #[error("store to read-only address {addr:#x} at pc {pc:#x} (clk {clk})")]
+    ReadOnlyWrite {
+        /// The read-only address that was written.
+        addr: u32,
+        /// The program counter of the faulting instruction.
+        pc: u32,
+        /// The clock of the faulting instruction.
+        clk: u32,
+    },
+
+    /// The stack pointer was set outside of the stack while [`Executor::check_stack`] is set.
+    #[error("stack pointer {sp:#x} is outside of the stack at pc {pc:#x} (clk {clk})")]
+    StackOutOfBounds {
+        /// The value written to the stack pointer.
+        sp: u32,
+        /// The program counter of the faulting instruction.
+        pc: u32,
+        /// The clock of the faulting instruction.
+        clk: u32,
+    },
+
+    /// A load or store computed an address inside the register file, which occupies the first
+    /// [`NUM_REGISTERS`] words of memory.
+    #[error("address {addr:#x} aliases the register file at pc {pc:#x} (clk {clk})")]
+    RegisterFileAliasing {
+        /// The address that was accessed.
+        addr: u32,
+        /// The program counter of the faulting instruction, or the start of the program if the
+        /// address is part of the memory image.
+        pc: u32,
+        /// The clock of the faulting instruction.
+        clk: u32,
+    },
+
+    /// A load read memory that was never written, with [`UninitPattern::Error`].
+    #[error("read of uninitialized address {addr:#x} at pc {pc:#x} (clk {clk})")]
+    UninitializedRead {
+        /// The word-aligned address that was read.
+        addr: u32,
+        /// The program counter of the load.
+        pc: u32,
+        /// The clock of the load.
+        clk: u32,
+    },
+
+    /// The program counter left the program without halting.
+    #[error("execution left the program at pc {0:#x} without halting")]
+    UnexpectedTermination(u32),
+
+    /// An instruction was fetched from outside of the program's code, e.g. when execution starts
+    /// outside of it or is stepped past its end.
+    #[error("fetch outside of the program at pc {pc:#x} (clk {clk})")]
+    FetchOutOfBounds {
+        /// The program counter of the fetch.
+        pc: u32,
+        /// The clock of the fetch.
+        clk: u32,
+    },
+}
+
+/// A structural problem in a program found by [`Executor::validate_program`].
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgramError {
+    /// A branch or jump with a static target leaves the program's code.
+    #[error("branch at pc {pc:#x} targets {target:#x}, outside of the program")]
+    TargetOutOfBounds {
+        /// The program counter of the branch or jump.
+        pc: u32,
+        /// The target of the branch or jump.
+        target: u32,
+    },
+
+    /// A register operand is not one of the 32 registers.
+    #[error("instruction at pc {pc:#x} uses register {register}")]
+    InvalidRegister {
+        /// The program counter of the instruction.
+        pc: u32,
+        /// The register operand.
+        register: u32,
+    },
+
+    /// The program contains an unimplemented instruction.
+    #[error("unimplemented instruction at pc {pc:#x}")]
+    Unimplemented {
+        /// The program counter of the instruction.
+        pc: u32,
+    },
+}
+
+/// The first difference found by [`Executor::run_checked`] between the execution and the expected
+/// events.
+#[derive(Error, Debug)]
+pub enum Divergence {
+    /// A field of a cpu event differs from the expected event.
+    #[error("{field} is {actual:#x} instead of {expected:#x} at pc {pc:#x} (clk {clk})")]
+    Value {
+        /// The name of the field of the [`CpuEvent`] that differs.
+        field: &'static str,
+        /// The value of the field in the expected event.
+        expected: u32,
+        /// The value of the field in the emitted event.
+        actual: u32,
+        /// The program counter of the emitted event.
+        pc: u32,
+        /// The clock of the emitted event.
+        clk: u32,
+    },
+
+    /// The execution emitted more cpu events than expected.
+    #[error("execution continued past the {0} expected cpu events")]
+    ExtraEvents(usize),
+
+    /// The execution finished before emitting all the expected cpu events.
+    #[error("execution finished after {actual} of the {expected} expected cpu events")]
+    MissingEvents {
+        /// The number of expected cpu events.
+        expected: usize,
+        /// The number of cpu events emitted.
+        actual: usize,
+    },
+
+    /// The execution failed.
+    #[error(transparent)]
+    Execution(#[from] ExecutionError),
+}
+
+/// The number of registers, which are stored in the first words of memory.
+pub const NUM_REGISTERS: u32 = 32;
+
+/// The initial value of the stack pointer set by the zkVM entrypoint. The stack grows down from
+/// here.
+pub const STACK_TOP: u32 = 0x0020_0400;
+
+/// The number of memory reads and writes of the cpu events, split between the register operands
+/// and the loads and stores.
+///
+/// See [`Executor::memory_event_counts_by_kind`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryEventCounts {
+    /// The number of reads of register operands.
+    pub register_reads: usize,
+    /// The number of writes of register operands.
+    pub register_writes: usize,
+    /// The number of reads by loads.
+    pub memory_reads: usize,
+    /// The number of writes by stores.
+    pub memory_writes: usize,
+}
+
+/// The order in which [`Executor::write_memory_region`] and [`Executor::read_memory_region`] pack
+/// bytes into words.
+///
+/// Memory words are always little-endian as in RISC-V, so big-endian bytes are swapped.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Endianness {
+    /// The first byte is the least significant byte of the word.
+    #[default]
+    Little,
+    /// The first byte is the most significant byte of the word.
+    Big,
+}
+
+impl Endianness {
+    /// Packs four bytes into a word.
+    #[must_use]
+    pub const fn to_word(self, bytes: [u8; 4]) -> u32 {
+        match self {
+            Endianness::Little => u32::from_le_bytes(bytes),
+            Endianness::Big => u32::from_be_bytes(bytes),
+        }
+    }
+
+    /// Unpacks a word into four bytes.
+    #[must_use]
+    pub const fn to_bytes(self, word: u32) -> [u8; 4] {
+        match self {
+            Endianness::Little => word.to_le_bytes(),
+            Endianness::Big => word.to_be_bytes(),
+        }
+    }
+}
+
+/// The format of a memory dump written by [`Executor::dump_memory`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DumpFormat {
+    /// Lines of 16 bytes prefixed by their address, like `hexdump`. Only the rows that contain an
+    /// initialized word are written, and uninitialized words in them are shown as zeros.
+    Hex,
+    /// The address and the value of each word, both as little-endian `u32`s.
+    Bin,
+    /// A JSON object mapping each hex address to the value of its word.
+    Json,
+}
+
+/// The behavior of the [``Executor``] when the program counter leaves the program without going
+/// through the `HALT` syscall (e.g. by running past the last instruction).
+///
+/// The `HALT` syscall ends the execution by jumping to pc 0, so leaving the program at pc 0 is
+/// always a regular end of the execution, whatever the policy.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FallOffPolicy {
+    /// Treat it as the end of the execution, as for hand-written programs without a `HALT`.
+    Halt,
+    /// Fail with [`ExecutionError::UnexpectedTermination`].
+    #[default]
+    Error,
+}
+
+/// What the [``Executor``] does when a load reads memory that was never written.
+///
+/// The registers, the memory image of the program and the memory written by hints are always
+/// initialized.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UninitPattern {
+    /// Read zero.
+    #[default]
+    Zero,
+    /// Fail with [`ExecutionError::UninitializedRead`].
+    Error,
 }
 
 macro_rules! assert_valid_memory_access {
@@ -144,6 +566,18 @@ impl<'a> Executor<'a> {
         Self::with_context(program, opts, SP1Context::default())
     }
 
+    /// Create a new [``Executor``] from a RV32IM ELF and options.
+    ///
+    /// The code and the memory image are loaded from the `PT_LOAD` segments of the ELF, and the
+    /// execution starts at its entrypoint.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the ELF is not a valid 32-bit RISC-V executable.
+    pub fn from_elf(elf: &[u8], opts: SP1CoreOpts) -> eyre::Result<Self> {
+        Ok(Self::new(Program::from(elf)?, opts))
+    }
+
     /// Create a new runtime from a program, options, and a context.
     ///
     /// # Panics
@@ -197,9 +631,143 @@ impl<'a> Executor<'a> {
             opts,
             max_cycles: context.max_cycles,
             touched_memory: HashSet::default(),
+            fall_off_policy: context.fall_off_policy,
+            uninit_pattern: UninitPattern::default(),
+            break_pc: None,
+            check_stack: false,
+            allow_misaligned: false,
+            readonly_regions: Vec::new(),
+            undo_depth: 0,
+            undo_log: VecDeque::new(),
+            undo_step: None,
+            trace_ring_capacity: 0,
+            trace_ring: VecDeque::new(),
+            watchpoints: HashMap::new(),
+            auto_defer: None,
+            loop_window: 0,
+            loop_states: VecDeque::new(),
+            memory_spill: None,
+        }
+    }
+
+    /// Keep the last `capacity` executed instructions, retrievable with
+    /// [`Executor::recent_instructions`].
+    #[must_use]
+    pub fn with_trace_ring(mut self, capacity: usize) -> Self {
+        self.trace_ring_capacity = capacity;
+        self.trace_ring = VecDeque::with_capacity(capacity);
+        self
+    }
+
+    /// Defer the precompile events to `sink` as soon as any of them reaches its threshold.
+    ///
+    /// After each syscall, if there are at least `thresholds.keccak` keccak events,
+    /// `thresholds.sha_extend` sha extend events, `thresholds.sha_compress` sha compress events,
+    /// or `thresholds.deferred` events of another precompile, all the precompile events are moved
+    /// out of the current record with [`ExecutionRecord::defer`] and passed to `sink`.
+    ///
+    /// The events passed to `sink` can't be rolled back, so deferring them also forgets the steps
+    /// that [`Executor::undo`] could revert.
+    #[must_use]
+    pub fn with_auto_defer(
+        mut self,
+        thresholds: SplitOpts,
+        sink: impl FnMut(ExecutionRecord) + Send + 'a,
+    ) -> Self {
+        self.auto_defer = Some((thresholds, Box::new(sink)));
+        self
+    }
+
+    /// Fail with [`ExecutionError::InfiniteLoopDetected`] when the program counter and the
+    /// registers repeat within `window` cycles.
+    ///
+    /// Memory isn't part of the state, so a loop that only makes progress through memory is
+    /// reported as well.
+    #[must_use]
+    pub fn with_loop_detection(mut self, window: usize) -> Self {
+        self.loop_window = window;
+        self.loop_states = VecDeque::with_capacity(window);
+        self
+    }
+
+    /// Set what to do when the program counter leaves the program without halting.
+    #[must_use]
+    pub fn with_fall_off_policy(mut self, policy: FallOffPolicy) -> Self {
+        self.fall_off_policy = policy;
+        self
+    }
+
+    /// Register the syscalls of the [`memory_syscall_map`].
+    ///
+    /// These syscalls aren't constrained by a chip yet, so the programs using them can be executed
+    /// but not proven.
+    #[must_use]
+    pub fn with_memory_syscalls(mut self) -> Self {
+        self.syscall_map.extend(memory_syscall_map());
+        self.max_syscall_cycles =
+            self.syscall_map.values().map(|syscall| syscall.num_extra_cycles()).max().unwrap_or(0);
+        self
+    }
+
+    /// Keep at most `capacity` pages of [`SPILL_PAGE_WORDS`] words of memory resident, spilling the
+    /// least recently used ones to `store` and loading them back when they are accessed again.
+    ///
+    /// This trades speed for footprint, for guests whose memory doesn't fit on the host.
+    /// Checkpointed execution loads all the pages back before taking each checkpoint. If `store`
+    /// fails, the execution fails with [`ExecutionError::MemorySpill`].
+    ///
+    /// [`SPILL_PAGE_WORDS`]: crate::SPILL_PAGE_WORDS
+    #[must_use]
+    pub fn with_memory_spill(mut self, capacity: usize, store: impl PageStore + 'static) -> Self {
+        self.memory_spill = Some(MemorySpill::new(capacity, store));
+        self
+    }
+
+    /// Load all the pages of memory spilled by [`Executor::with_memory_spill`] back into
+    /// `state.memory`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a page can't be loaded back from the store.
+    pub fn unspill(&mut self) -> Result<(), ExecutionError> {
+        if let Some(spill) = &mut self.memory_spill {
+            spill
+                .load_all(&mut self.state.memory)
+                .map_err(|err| ExecutionError::MemorySpill(err.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Fail if the store of the spilled memory failed since the last check.
+    fn check_spill(&mut self) -> Result<(), ExecutionError> {
+        match self.memory_spill.as_mut().and_then(MemorySpill::take_failure) {
+            Some(err) => Err(ExecutionError::MemorySpill(err.to_string())),
+            None => Ok(()),
+        }
+    }
+
+    /// Make the page of `addr` resident, if the memory is bounded.
+    #[inline]
+    pub(crate) fn page_in(&mut self, addr: u32) {
+        if let Some(spill) = &mut self.memory_spill {
+            spill.access(&mut self.state.memory, addr);
         }
     }
 
+    /// Get the program counter of the `EBREAK` that stopped the execution, or `None` if the
+    /// execution didn't stop at a breakpoint.
+    #[must_use]
+    pub const fn break_pc(&self) -> Option<u32> {
+        self.break_pc
+    }
+
+    /// Get the most recently executed instructions along with their program counters, oldest
+    /// first.
+    #[must_use]
+    pub fn recent_instructions(&self) -> Vec<(u32, Instruction)> {
+        self.trace_ring.iter().copied().collect()
+    }
+
     /// Invokes a hook with the given file descriptor `fd` with the data `buf`.
     ///
     /// # Errors
@@ -228,6 +796,165 @@ impl<'a> Executor<'a> {
         runtime
     }
 
+    /// Reset the runtime to the start of the program so that it can be run again, keeping the
+    /// program, the options and the input and proof streams.
+    ///
+    /// The registers and the memory are cleared and the memory image is loaded again when the
+    /// execution restarts at `pc_start` (an entrypoint set with [`Executor::set_entry`] must be set
+    /// again). The events, records, report and debugging state of the previous run are discarded.
+    pub fn rewind(&mut self) {
+        let input_stream = std::mem::take(&mut self.state.input_stream);
+        let proof_stream = std::mem::take(&mut self.state.proof_stream);
+        self.state = ExecutionState::new(self.program.pc_start);
+        self.state.input_stream = input_stream;
+        self.state.proof_stream = proof_stream;
+
+        self.record = ExecutionRecord { program: self.program.clone(), ..Default::default() };
+        self.records.clear();
+        self.memory_accesses = MemoryAccessRecord::default();
+        self.cycle_tracker.clear();
+        self.io_buf.clear();
+        self.unconstrained = false;
+        self.unconstrained_state = ForkState::default();
+        self.report = ExecutionReport::default();
+        self.touched_memory.clear();
+        self.undo_log.clear();
+        self.undo_step = None;
+        self.trace_ring.clear();
+        self.loop_states.clear();
+        self.break_pc = None;
+        if let Some(spill) = &mut self.memory_spill {
+            spill.clear();
+        }
+    }
+
+    /// Set the program counter at which execution starts.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `pc` is not word-aligned or does not point to an
+    /// instruction of the program.
+    pub fn set_entry(&mut self, pc: u32) -> Result<(), ExecutionError> {
+        let offset = pc.wrapping_sub(self.program.pc_base);
+        if pc % 4 != 0 || offset >= (self.program.instructions.len() * 4) as u32 {
+            return Err(ExecutionError::InvalidEntrypoint(pc));
+        }
+        self.state.pc = pc;
+        Ok(())
+    }
+
+    /// Checks the program for structural problems without executing it.
+    ///
+    /// Branches and `JAL`s must target an instruction of the program (the target of `JALR`
+    /// depends on a register and isn't checked), register operands must be below 32, and the
+    /// program must not contain `UNIMP`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ProgramError`] for the first problem found, in program order.
+    pub fn validate_program(&self) -> Result<(), ProgramError> {
+        let pc_base = self.program.pc_base;
+        let code_len = (self.program.instructions.len() * 4) as u32;
+        for (i, instruction) in self.program.instructions.iter().enumerate() {
+            let pc = pc_base + 4 * i as u32;
+            if instruction.opcode == Opcode::UNIMP {
+                return Err(ProgramError::Unimplemented { pc });
+            }
+
+            let registers = [
+                Some(instruction.op_a),
+                (!instruction.imm_b).then_some(instruction.op_b),
+                (!instruction.imm_c).then_some(instruction.op_c),
+            ];
+            if let Some(register) = registers.into_iter().flatten().find(|&register| register >= 32)
+            {
+                return Err(ProgramError::InvalidRegister { pc, register });
+            }
+
+            if let (Some(target), _) = Program::successors(pc, instruction) {
+                if target % 4 != 0 || target.wrapping_sub(pc_base) >= code_len {
+                    return Err(ProgramError::TargetOutOfBounds { pc, target });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Counts the occurrences of each opcode in the instructions of the program, without executing
+    /// it.
+    ///
+    /// Unlike the opcode counts of the [`ExecutionReport`], each instruction is counted once no
+    /// matter how many times it would run. Immediate forms (e.g. `ADDI`) are counted under their
+    /// register opcode.
+    #[must_use]
+    pub fn static_opcode_mix(&self) -> BTreeMap<Opcode, usize> {
+        let mut mix = BTreeMap::new();
+        for instruction in &self.program.instructions {
+            *mix.entry(instruction.opcode).or_insert(0) += 1;
+        }
+        mix
+    }
+
+    /// Returns the word-aligned addresses whose first access by a load or store is a load of memory
+    /// that was never written, in the order of these loads.
+    ///
+    /// The accesses are replayed from the cpu events of the records, so the events must have been
+    /// emitted. The memory image of the program and the memory written by hints count as written,
+    /// and the accesses made by syscalls are not replayed.
+    #[must_use]
+    pub fn read_before_write_addresses(&self) -> Vec<u32> {
+        let mut accessed = HashSet::<u32, BuildNoHashHasher<u32>>::default();
+        let mut addresses = Vec::new();
+        let events = self.records.iter().chain([&self.record]).flat_map(|r| &r.cpu_events);
+        for event in events {
+            let Some(memory_record) = event.memory_record else {
+                continue;
+            };
+            let addr = align(event.b.wrapping_add(event.c));
+            if accessed.insert(addr)
+                && matches!(memory_record, MemoryRecordEnum::Read(_))
+                && !self.program.memory_image.contains_key(&addr)
+                && !self.state.uninitialized_memory.contains_key(&addr)
+            {
+                addresses.push(addr);
+            }
+        }
+        addresses
+    }
+
+    /// Returns the number of memory reads and writes of the cpu events, as `(reads, writes)`.
+    ///
+    /// See [`Executor::memory_event_counts_by_kind`].
+    #[must_use]
+    pub fn memory_event_counts(&self) -> (usize, usize) {
+        let counts = self.memory_event_counts_by_kind();
+        (counts.register_reads + counts.memory_reads, counts.register_writes + counts.memory_writes)
+    }
+
+    /// Returns the number of memory reads and writes of the cpu events, split between the register
+    /// operands and the loads and stores.
+    ///
+    /// The events must have been emitted. The accesses made by syscalls are not counted.
+    #[must_use]
+    pub fn memory_event_counts_by_kind(&self) -> MemoryEventCounts {
+        let mut counts = MemoryEventCounts::default();
+        let events = self.records.iter().chain([&self.record]).flat_map(|r| &r.cpu_events);
+        for event in events {
+            for record in [event.a_record, event.b_record, event.c_record].into_iter().flatten() {
+                match record {
+                    MemoryRecordEnum::Read(_) => counts.register_reads += 1,
+                    MemoryRecordEnum::Write(_) => counts.register_writes += 1,
+                }
+            }
+            match event.memory_record {
+                Some(MemoryRecordEnum::Read(_)) => counts.memory_reads += 1,
+                Some(MemoryRecordEnum::Write(_)) => counts.memory_writes += 1,
+                None => {}
+            }
+        }
+        counts
+    }
+
     /// Get the current values of the registers.
     #[must_use]
     pub fn registers(&mut self) -> [u32; 32] {
@@ -258,6 +985,7 @@ impl<'a> Executor<'a> {
     #[must_use]
     pub fn word(&mut self, addr: u32) -> u32 {
         self.touched_memory.insert(addr);
+        self.page_in(addr);
         match self.state.memory.get(&addr) {
             Some(record) => record.value,
             None => 0,
@@ -271,30 +999,160 @@ impl<'a> Executor<'a> {
         (word >> ((addr % 4) * 8)) as u8
     }
 
-    /// Get the current timestamp for a given memory access position.
-    #[must_use]
-    pub const fn timestamp(&self, position: &MemoryAccessPosition) -> u32 {
-        self.state.clk + *position as u32
-    }
-
-    /// Get the current shard.
-    #[must_use]
-    #[inline]
-    pub fn shard(&self) -> u32 {
-        self.state.current_shard
+    /// Sets the initial value of the words starting at `addr` to `bytes`, packed into words with the
+    /// given endianness and padded with zeros to a whole number of words.
+    ///
+    /// Like hinted data, the words are part of the initial memory of the execution, so they must be
+    /// written before they are accessed.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `addr` is not word-aligned or if one of the words was already
+    /// accessed.
+    pub fn write_memory_region(&mut self, addr: u32, bytes: &[u8], endianness: Endianness) {
+        assert_eq!(addr % 4, 0, "memory region address {addr:#x} is not word-aligned");
+        for (i, chunk) in bytes.chunks(4).enumerate() {
+            let mut word = [0; 4];
+            word[..chunk.len()].copy_from_slice(chunk);
+            let word_addr = addr + 4 * i as u32;
+            self.page_in(word_addr);
+            assert!(
+                !self.state.memory.contains_key(&word_addr),
+                "memory region address {word_addr:#x} was already accessed"
+            );
+            self.state.uninitialized_memory.insert(word_addr, endianness.to_word(word));
+        }
     }
 
-    /// Get the current channel.
-    #[must_use]
-    #[inline]
-    pub fn channel(&self) -> u8 {
-        self.state.channel
+    /// Get the `len` bytes starting at `addr`, unpacked from words with the given endianness.
+    ///
+    /// Words that weren't accessed yet read as their initial value.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a spilled page of the region can't be loaded back.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `addr` is not word-aligned.
+    pub fn read_memory_region(
+        &mut self,
+        addr: u32,
+        len: usize,
+        endianness: Endianness,
+    ) -> Result<Vec<u8>, ExecutionError> {
+        assert_eq!(addr % 4, 0, "memory region address {addr:#x} is not word-aligned");
+        let mut bytes = Vec::with_capacity(len.next_multiple_of(4));
+        for i in 0..len.div_ceil(4) as u32 {
+            let word_addr = addr + 4 * i;
+            self.page_in(word_addr);
+            let word = match self.state.memory.get(&word_addr) {
+                Some(record) => record.value,
+                None => self.state.uninitialized_memory.get(&word_addr).copied().unwrap_or(0),
+            };
+            bytes.extend(endianness.to_bytes(word));
+        }
+        self.check_spill()?;
+        bytes.truncate(len);
+        Ok(bytes)
+    }
+
+    /// Writes the memory of the runtime to `w` in the given format, excluding the registers.
+    ///
+    /// The pages spilled with [`Executor::with_memory_spill`] are loaded back first.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if writing to `w` or loading back a spilled page fails.
+    pub fn dump_memory(&mut self, fmt: DumpFormat, mut w: impl Write) -> std::io::Result<()> {
+        if let Some(spill) = &mut self.memory_spill {
+            spill.load_all(&mut self.state.memory)?;
+        }
+        let memory = self
+            .state
+            .memory
+            .iter()
+            .filter(|(addr, _)| **addr >= 32)
+            .map(|(addr, record)| (*addr, record.value))
+            .collect::<BTreeMap<_, _>>();
+
+        match fmt {
+            DumpFormat::Hex => {
+                let rows = memory.keys().map(|addr| addr & !0xf).collect::<BTreeSet<_>>();
+                for row in rows {
+                    write!(w, "{row:08x}:")?;
+                    for addr in (row..=row + 12).step_by(4) {
+                        let word = memory.get(&addr).copied().unwrap_or(0);
+                        for byte in word.to_le_bytes() {
+                            write!(w, " {byte:02x}")?;
+                        }
+                    }
+                    writeln!(w)?;
+                }
+            }
+            DumpFormat::Bin => {
+                for (addr, value) in &memory {
+                    w.write_all(&addr.to_le_bytes())?;
+                    w.write_all(&value.to_le_bytes())?;
+                }
+            }
+            DumpFormat::Json => {
+                let entries = memory.iter().map(|(addr, value)| format!("\"{addr:#x}\":{value}"));
+                writeln!(w, "{{{}}}", entries.collect::<Vec<_>>().join(","))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Marks the `len` bytes starting at `base` as read-only, so that stores to them fail with
+    /// [`ExecutionError::ReadOnlyWrite`].
+    ///
+    /// Only stores made by instructions are checked; syscalls can still write to the region.
+    pub fn mark_readonly(&mut self, base: u32, len: u32) {
+        self.readonly_regions.push((base, len));
+    }
+
+    /// Calls `on_hit` with the runtime and the new value every time the word at `addr` is
+    /// written, replacing any watchpoint already installed on `addr`.
+    ///
+    /// The callback runs after the write is applied. Registers can be watched through their
+    /// addresses, and writes made by syscalls are caught as well.
+    pub fn add_watchpoint(&mut self, addr: u32, on_hit: Watchpoint<'a>) {
+        self.watchpoints.insert(addr, on_hit);
+    }
+
+    /// Returns the first of the `size` bytes starting at `addr` that lies in a read-only region.
+    fn readonly_byte(&self, addr: u32, size: u32) -> Option<u32> {
+        (addr..addr.saturating_add(size)).find(|byte| {
+            self.readonly_regions.iter().any(|&(base, len)| byte.wrapping_sub(base) < len)
+        })
+    }
+
+    /// Get the current timestamp for a given memory access position.
+    #[must_use]
+    pub const fn timestamp(&self, position: &MemoryAccessPosition) -> u32 {
+        self.state.clk + *position as u32
+    }
+
+    /// Get the current shard.
+    #[must_use]
+    #[inline]
+    pub fn shard(&self) -> u32 {
+        self.state.current_shard
+    }
+
+    /// Get the current channel.
+    #[must_use]
+    #[inline]
+    pub fn channel(&self) -> u8 {
+        self.state.channel
     }
 
     /// Read a word from memory and create an access record.
     pub fn mr(&mut self, addr: u32, shard: u32, timestamp: u32) -> MemoryReadRecord {
         // Get the memory record entry.
         self.touched_memory.insert(addr);
+        self.page_in(addr);
         let entry = self.state.memory.entry(addr);
 
         // If we're in unconstrained mode, we don't want to modify state, so we'll save the
@@ -307,6 +1165,15 @@ impl<'a> Executor<'a> {
             self.unconstrained_state.memory_diff.entry(addr).or_insert(record.copied());
         }
 
+        // If we're stepping, save the original state so that the step can be undone.
+        if let Some(undo) = &mut self.undo_step {
+            let record = match entry {
+                Entry::Occupied(ref entry) => Some(entry.get()),
+                Entry::Vacant(_) => None,
+            };
+            undo.memory_diff.entry(addr).or_insert(record.copied());
+        }
+
         // If it's the first time accessing this address, initialize previous values.
         let record: &mut MemoryRecord = match entry {
             Entry::Occupied(entry) => entry.into_mut(),
@@ -330,6 +1197,7 @@ impl<'a> Executor<'a> {
     pub fn mw(&mut self, addr: u32, value: u32, shard: u32, timestamp: u32) -> MemoryWriteRecord {
         // Get the memory record entry.
         self.touched_memory.insert(addr);
+        self.page_in(addr);
         let entry = self.state.memory.entry(addr);
 
         // If we're in unconstrained mode, we don't want to modify state, so we'll save the
@@ -342,6 +1210,15 @@ impl<'a> Executor<'a> {
             self.unconstrained_state.memory_diff.entry(addr).or_insert(record.copied());
         }
 
+        // If we're stepping, save the original state so that the step can be undone.
+        if let Some(undo) = &mut self.undo_step {
+            let record = match entry {
+                Entry::Occupied(ref entry) => Some(entry.get()),
+                Entry::Vacant(_) => None,
+            };
+            undo.memory_diff.entry(addr).or_insert(record.copied());
+        }
+
         // If it's the first time accessing this address, initialize previous values.
         let record: &mut MemoryRecord = match entry {
             Entry::Occupied(entry) => entry.into_mut(),
@@ -359,6 +1236,14 @@ impl<'a> Executor<'a> {
         record.shard = shard;
         record.timestamp = timestamp;
 
+        // If the address is watched, invoke its callback with the new value.
+        if !self.watchpoints.is_empty() {
+            if let Some(mut on_hit) = self.watchpoints.remove(&addr) {
+                on_hit(self, value);
+                self.watchpoints.insert(addr, on_hit);
+            }
+        }
+
         // Construct the memory write record.
         MemoryWriteRecord::new(value, shard, timestamp, prev_value, prev_shard, prev_timestamp)
     }
@@ -420,6 +1305,10 @@ impl<'a> Executor<'a> {
     }
 
     /// Read from a register.
+    ///
+    /// Reads of %x0 still go through memory: the CPU chip constrains a memory access for every
+    /// register operand, so skipping it would make the shard unprovable. Since [`Executor::rw`]
+    /// always writes 0 to %x0, these reads always yield 0.
     pub fn rr(&mut self, register: Register, position: MemoryAccessPosition) -> u32 {
         self.mr_cpu(register as u32, position)
     }
@@ -564,23 +1453,105 @@ impl<'a> Executor<'a> {
     }
 
     /// Fetch the input operand values for a load instruction.
-    fn load_rr(&mut self, instruction: &Instruction) -> (Register, u32, u32, u32, u32) {
+    fn load_rr(
+        &mut self,
+        instruction: &Instruction,
+    ) -> Result<(Register, u32, u32, u32, u32), ExecutionError> {
         let (rd, rs1, imm) = instruction.i_type();
         let (b, c) = (self.rr(rs1, MemoryAccessPosition::B), imm);
         let addr = b.wrapping_add(c);
+        self.check_register_aliasing(addr)?;
+        self.page_in(align(addr));
+        if self.uninit_pattern == UninitPattern::Error
+            && !self.state.memory.contains_key(&align(addr))
+            && !self.state.uninitialized_memory.contains_key(&align(addr))
+        {
+            return Err(ExecutionError::UninitializedRead {
+                addr: align(addr),
+                pc: self.state.pc,
+                clk: self.state.clk,
+            });
+        }
         let memory_value = self.mr_cpu(align(addr), MemoryAccessPosition::Memory);
-        (rd, b, c, addr, memory_value)
+        Ok((rd, b, c, addr, memory_value))
     }
 
     /// Fetch the input operand values for a store instruction.
-    fn store_rr(&mut self, instruction: &Instruction) -> (u32, u32, u32, u32, u32) {
+    fn store_rr(
+        &mut self,
+        instruction: &Instruction,
+    ) -> Result<(u32, u32, u32, u32, u32), ExecutionError> {
         let (rs1, rs2, imm) = instruction.s_type();
         let c = imm;
         let b = self.rr(rs2, MemoryAccessPosition::B);
         let a = self.rr(rs1, MemoryAccessPosition::A);
         let addr = b.wrapping_add(c);
+        self.check_register_aliasing(addr)?;
         let memory_value = self.word(align(addr));
-        (a, b, c, addr, memory_value)
+        Ok((a, b, c, addr, memory_value))
+    }
+
+    /// Returns the `len` bytes starting at `addr`, where `word` is the word containing `addr`. If the
+    /// bytes span two words, the rest of them are read from the next word.
+    fn load_bytes(&mut self, addr: u32, word: u32, len: u32) -> u32 {
+        let offset = addr % 4;
+        let next = if offset + len > 4 { self.word(align(addr).wrapping_add(4)) } else { 0 };
+        let bytes = ((u64::from(next) << 32) | u64::from(word)) >> (8 * offset);
+        (bytes & ((1 << (8 * len)) - 1)) as u32
+    }
+
+    /// Fails unless `addr` is a multiple of `alignment` or misaligned accesses are allowed.
+    fn check_alignment(
+        &self,
+        opcode: Opcode,
+        addr: u32,
+        alignment: u32,
+        pc: u32,
+        clk: u32,
+    ) -> Result<(), ExecutionError> {
+        if addr % alignment == 0 {
+            Ok(())
+        } else if !self.allow_misaligned {
+            Err(ExecutionError::MisalignedMemoryAccess { opcode, addr, pc, clk })
+        } else if self.emit_events {
+            Err(ExecutionError::UnprovableMisalignedAccess { opcode, addr, pc, clk })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns `word`, the word containing `addr`, with the `len` bytes starting at `addr` set to
+    /// the low bytes of `value`. If the bytes span two words, the rest of them are written to the
+    /// next word.
+    fn store_bytes(&mut self, addr: u32, word: u32, len: u32, value: u32) -> u32 {
+        let offset = addr % 4;
+        let mask = ((1u64 << (8 * len)) - 1) << (8 * offset);
+        let value = u64::from(value) << (8 * offset);
+        if offset + len > 4 {
+            let next_addr = align(addr).wrapping_add(4);
+            let next = self.word(next_addr);
+            let next = (((u64::from(next) << 32) & !mask) | (value & mask)) >> 32;
+            self.mw(
+                next_addr,
+                next as u32,
+                self.shard(),
+                self.timestamp(&MemoryAccessPosition::Memory),
+            );
+        }
+        ((u64::from(word) & !mask) | (value & mask)) as u32
+    }
+
+    /// Checks that the address computed by a load or store doesn't land in the register file,
+    /// which can happen when the base and the offset wrap around.
+    fn check_register_aliasing(&self, addr: u32) -> Result<(), ExecutionError> {
+        if addr < NUM_REGISTERS {
+            return Err(ExecutionError::RegisterFileAliasing {
+                addr,
+                pc: self.state.pc,
+                clk: self.state.clk,
+            });
+        }
+        Ok(())
     }
 
     /// Fetch the input operand values for a branch instruction.
@@ -593,9 +1564,45 @@ impl<'a> Executor<'a> {
     }
 
     /// Fetch the instruction at the current program counter.
-    fn fetch(&self) -> Instruction {
-        let idx = ((self.state.pc - self.program.pc_base) / 4) as usize;
-        self.program.instructions[idx]
+    ///
+    /// Instructions are decoded once when the [`Program`] is built, so fetching is a plain index
+    /// into [`Program::instructions`]. Code does not live in the executor's memory, which means
+    /// stores to the code region never change the fetched instruction.
+    fn fetch(&self) -> Result<Instruction, ExecutionError> {
+        let idx = (self.state.pc.wrapping_sub(self.program.pc_base) / 4) as usize;
+        self.program
+            .instructions
+            .get(idx)
+            .copied()
+            .ok_or(ExecutionError::FetchOutOfBounds { pc: self.state.pc, clk: self.state.clk })
+    }
+
+    /// Pass the precompile events to the auto defer sink if the events of `syscall` reached their
+    /// threshold.
+    ///
+    /// The deferred events can't be restored by [`Executor::undo`], so the steps before are
+    /// forgotten.
+    fn auto_defer(&mut self, syscall: SyscallCode) {
+        let Some((thresholds, sink)) = &mut self.auto_defer else {
+            return;
+        };
+        // Only the events of `syscall` changed since the last check.
+        let Some(chip) = ChipKind::from_syscall(syscall) else {
+            return;
+        };
+        let threshold = match chip {
+            ChipKind::KeccakPermute => thresholds.keccak,
+            ChipKind::ShaExtend => thresholds.sha_extend,
+            ChipKind::ShaCompress => thresholds.sha_compress,
+            _ => thresholds.deferred,
+        };
+        if self.record.num_events(chip) >= threshold {
+            let mut deferred = self.record.defer();
+            deferred.program = self.program.clone();
+            sink(deferred);
+            self.undo_log.clear();
+            self.undo_step = None;
+        }
     }
 
     /// Execute the given instruction over the current state of the runtime.
@@ -663,12 +1670,12 @@ impl<'a> Executor<'a> {
             }
             Opcode::SRA => {
                 (rd, b, c) = self.alu_rr(instruction);
-                a = (b as i32).wrapping_shr(c) as u32;
+                a = as_signed(b).wrapping_shr(c) as u32;
                 self.alu_rw(instruction, rd, a, b, c, lookup_id);
             }
             Opcode::SLT => {
                 (rd, b, c) = self.alu_rr(instruction);
-                a = if (b as i32) < (c as i32) { 1 } else { 0 };
+                a = if as_signed(b) < as_signed(c) { 1 } else { 0 };
                 self.alu_rw(instruction, rd, a, b, c, lookup_id);
             }
             Opcode::SLTU => {
@@ -679,52 +1686,38 @@ impl<'a> Executor<'a> {
 
             // Load instructions.
             Opcode::LB => {
-                (rd, b, c, addr, memory_read_value) = self.load_rr(instruction);
+                (rd, b, c, addr, memory_read_value) = self.load_rr(instruction)?;
                 let value = (memory_read_value).to_le_bytes()[(addr % 4) as usize];
                 a = ((value as i8) as i32) as u32;
                 memory_store_value = Some(memory_read_value);
                 self.rw(rd, a);
             }
             Opcode::LH => {
-                (rd, b, c, addr, memory_read_value) = self.load_rr(instruction);
-                if addr % 2 != 0 {
-                    return Err(ExecutionError::InvalidMemoryAccess(Opcode::LH, addr));
-                }
-                let value = match (addr >> 1) % 2 {
-                    0 => memory_read_value & 0x0000_FFFF,
-                    1 => (memory_read_value & 0xFFFF_0000) >> 16,
-                    _ => unreachable!(),
-                };
+                (rd, b, c, addr, memory_read_value) = self.load_rr(instruction)?;
+                self.check_alignment(Opcode::LH, addr, 2, pc, clk)?;
+                let value = self.load_bytes(addr, memory_read_value, 2);
                 a = ((value as i16) as i32) as u32;
                 memory_store_value = Some(memory_read_value);
                 self.rw(rd, a);
             }
             Opcode::LW => {
-                (rd, b, c, addr, memory_read_value) = self.load_rr(instruction);
-                if addr % 4 != 0 {
-                    return Err(ExecutionError::InvalidMemoryAccess(Opcode::LW, addr));
-                }
-                a = memory_read_value;
+                (rd, b, c, addr, memory_read_value) = self.load_rr(instruction)?;
+                self.check_alignment(Opcode::LW, addr, 4, pc, clk)?;
+                a = self.load_bytes(addr, memory_read_value, 4);
                 memory_store_value = Some(memory_read_value);
                 self.rw(rd, a);
             }
             Opcode::LBU => {
-                (rd, b, c, addr, memory_read_value) = self.load_rr(instruction);
+                (rd, b, c, addr, memory_read_value) = self.load_rr(instruction)?;
                 let value = (memory_read_value).to_le_bytes()[(addr % 4) as usize];
                 a = value as u32;
                 memory_store_value = Some(memory_read_value);
                 self.rw(rd, a);
             }
             Opcode::LHU => {
-                (rd, b, c, addr, memory_read_value) = self.load_rr(instruction);
-                if addr % 2 != 0 {
-                    return Err(ExecutionError::InvalidMemoryAccess(Opcode::LHU, addr));
-                }
-                let value = match (addr >> 1) % 2 {
-                    0 => memory_read_value & 0x0000_FFFF,
-                    1 => (memory_read_value & 0xFFFF_0000) >> 16,
-                    _ => unreachable!(),
-                };
+                (rd, b, c, addr, memory_read_value) = self.load_rr(instruction)?;
+                self.check_alignment(Opcode::LHU, addr, 2, pc, clk)?;
+                let value = self.load_bytes(addr, memory_read_value, 2);
                 a = (value as u16) as u32;
                 memory_store_value = Some(memory_read_value);
                 self.rw(rd, a);
@@ -732,7 +1725,10 @@ impl<'a> Executor<'a> {
 
             // Store instructions.
             Opcode::SB => {
-                (a, b, c, addr, memory_read_value) = self.store_rr(instruction);
+                (a, b, c, addr, memory_read_value) = self.store_rr(instruction)?;
+                if let Some(addr) = self.readonly_byte(addr, 1) {
+                    return Err(ExecutionError::ReadOnlyWrite { addr, pc, clk });
+                }
                 let value = match addr % 4 {
                     0 => (a & 0x0000_00FF) + (memory_read_value & 0xFFFF_FF00),
                     1 => ((a & 0x0000_00FF) << 8) + (memory_read_value & 0xFFFF_00FF),
@@ -744,24 +1740,22 @@ impl<'a> Executor<'a> {
                 self.mw_cpu(align(addr), value, MemoryAccessPosition::Memory);
             }
             Opcode::SH => {
-                (a, b, c, addr, memory_read_value) = self.store_rr(instruction);
-                if addr % 2 != 0 {
-                    return Err(ExecutionError::InvalidMemoryAccess(Opcode::SH, addr));
+                (a, b, c, addr, memory_read_value) = self.store_rr(instruction)?;
+                self.check_alignment(Opcode::SH, addr, 2, pc, clk)?;
+                if let Some(addr) = self.readonly_byte(addr, 2) {
+                    return Err(ExecutionError::ReadOnlyWrite { addr, pc, clk });
                 }
-                let value = match (addr >> 1) % 2 {
-                    0 => (a & 0x0000_FFFF) + (memory_read_value & 0xFFFF_0000),
-                    1 => ((a & 0x0000_FFFF) << 16) + (memory_read_value & 0x0000_FFFF),
-                    _ => unreachable!(),
-                };
+                let value = self.store_bytes(addr, memory_read_value, 2, a);
                 memory_store_value = Some(value);
                 self.mw_cpu(align(addr), value, MemoryAccessPosition::Memory);
             }
             Opcode::SW => {
-                (a, b, c, addr, _) = self.store_rr(instruction);
-                if addr % 4 != 0 {
-                    return Err(ExecutionError::InvalidMemoryAccess(Opcode::SW, addr));
+                (a, b, c, addr, memory_read_value) = self.store_rr(instruction)?;
+                self.check_alignment(Opcode::SW, addr, 4, pc, clk)?;
+                if let Some(addr) = self.readonly_byte(addr, 4) {
+                    return Err(ExecutionError::ReadOnlyWrite { addr, pc, clk });
                 }
-                let value = a;
+                let value = self.store_bytes(addr, memory_read_value, 4, a);
                 memory_store_value = Some(value);
                 self.mw_cpu(align(addr), value, MemoryAccessPosition::Memory);
             }
@@ -781,13 +1775,13 @@ impl<'a> Executor<'a> {
             }
             Opcode::BLT => {
                 (a, b, c) = self.branch_rr(instruction);
-                if (a as i32) < (b as i32) {
+                if as_signed(a) < as_signed(b) {
                     next_pc = self.state.pc.wrapping_add(c);
                 }
             }
             Opcode::BGE => {
                 (a, b, c) = self.branch_rr(instruction);
-                if (a as i32) >= (b as i32) {
+                if as_signed(a) >= as_signed(b) {
                     next_pc = self.state.pc.wrapping_add(c);
                 }
             }
@@ -817,7 +1811,9 @@ impl<'a> Executor<'a> {
                 (b, c) = (self.rr(rs1, MemoryAccessPosition::B), imm);
                 a = self.state.pc + 4;
                 self.rw(rd, a);
-                next_pc = b.wrapping_add(c);
+                // The least-significant bit of the target is cleared. See 2.5 Control Transfer
+                // Instructions of the RISC-V spec.
+                next_pc = b.wrapping_add(c) & !1;
             }
 
             // Upper immediate instructions.
@@ -832,16 +1828,39 @@ impl<'a> Executor<'a> {
             Opcode::ECALL => {
                 // We peek at register x5 to get the syscall id. The reason we don't `self.rr` this
                 // register is that we write to it later.
-                let t0 = Register::X5;
-                let syscall_id = self.register(t0);
-                c = self.rr(Register::X11, MemoryAccessPosition::C);
-                b = self.rr(Register::X10, MemoryAccessPosition::B);
-                let syscall = SyscallCode::from_u32(syscall_id);
+                let syscall_id = SyscallAbi::number(self);
+                c = self.rr(SyscallAbi::ARGS[1], MemoryAccessPosition::C);
+                b = self.rr(SyscallAbi::ARGS[0], MemoryAccessPosition::B);
+                let Some(syscall) = SyscallCode::try_from_u32(syscall_id) else {
+                    return Err(ExecutionError::UnknownSyscall { syscall_id, pc, clk });
+                };
 
                 if self.print_report && !self.unconstrained {
                     self.report.syscall_counts.entry(syscall).and_modify(|c| *c += 1).or_insert(1);
                 }
 
+                // The memory syscalls work on whole words.
+                let misaligned = match syscall {
+                    SyscallCode::MEMCPY | SyscallCode::MEMMOVE => {
+                        [b, c, SyscallAbi::arg(self, 2)].into_iter().find(|arg| arg % 4 != 0)
+                    }
+                    SyscallCode::MEMSET => {
+                        [b, SyscallAbi::arg(self, 2)].into_iter().find(|arg| arg % 4 != 0)
+                    }
+                    _ => None,
+                };
+                if let Some(arg) = misaligned {
+                    return Err(ExecutionError::MisalignedMemorySyscall { syscall, arg, pc, clk });
+                }
+
+                // `MEMCPY` requires disjoint ranges, overlapping copies must use `MEMMOVE`.
+                if syscall == SyscallCode::MEMCPY {
+                    let (dst, src, len) = (b, c, SyscallAbi::arg(self, 2));
+                    if dst < src.wrapping_add(len) && src < dst.wrapping_add(len) {
+                        return Err(ExecutionError::OverlappingMemcpy { dst, src, len, pc, clk });
+                    }
+                }
+
                 let syscall_impl = self.get_syscall(syscall).cloned();
                 let mut precompile_rt = SyscallContext::new(self);
                 precompile_rt.syscall_lookup_id = syscall_lookup_id;
@@ -870,14 +1889,14 @@ impl<'a> Executor<'a> {
                             precompile_rt.exit_code,
                         )
                     } else {
-                        return Err(ExecutionError::UnsupportedSyscall(syscall_id));
+                        return Err(ExecutionError::UnknownSyscall { syscall_id, pc, clk });
                     };
 
                 // Allow the syscall impl to modify state.clk/pc (exit unconstrained does this)
                 clk = self.state.clk;
                 pc = self.state.pc;
 
-                self.rw(t0, a);
+                SyscallAbi::set_return(self, a);
                 next_pc = precompile_next_pc;
                 self.state.clk += precompile_cycles;
                 exit_code = returned_exit_code;
@@ -893,9 +1912,11 @@ impl<'a> Executor<'a> {
                 let nonce = (((*syscall_count as usize) % threshold) * multiplier) as u32;
                 self.record.nonce_lookup.insert(syscall_lookup_id, nonce);
                 *syscall_count += 1;
+
+                self.auto_defer(syscall);
             }
             Opcode::EBREAK => {
-                return Err(ExecutionError::Breakpoint());
+                self.break_pc = Some(pc);
             }
 
             // Multiply instructions.
@@ -916,6 +1937,7 @@ impl<'a> Executor<'a> {
             }
             Opcode::MULHSU => {
                 (rd, b, c) = self.alu_rr(instruction);
+                // `rs1` (b) is signed and `rs2` (c) is unsigned, so only b is sign-extended.
                 a = (((b as i32) as i64).wrapping_mul(c as i64) >> 32) as u32;
                 self.alu_rw(instruction, rd, a, b, c, lookup_id);
             }
@@ -958,10 +1980,26 @@ impl<'a> Executor<'a> {
 
             // See https://github.com/riscv-non-isa/riscv-asm-manual/blob/master/riscv-asm.md#instruction-aliases
             Opcode::UNIMP => {
-                return Err(ExecutionError::Unimplemented());
+                return Err(ExecutionError::UnimplementedInstruction { pc, clk });
+            }
+        }
+
+        // The registers are on the first page of memory, which is never spilled.
+        if self.check_stack {
+            let sp = self.state.memory.get(&(Register::X2 as u32)).map_or(0, |record| record.value);
+            if sp > STACK_TOP {
+                return Err(ExecutionError::StackOutOfBounds { sp, pc, clk });
             }
         }
 
+        // Branch and jump targets must be aligned, otherwise the next fetch would silently round
+        // the program counter down.
+        if (instruction.is_branch_instruction() || instruction.is_jump_instruction())
+            && next_pc % 4 != 0
+        {
+            return Err(ExecutionError::MisalignedPc { target: next_pc, pc, clk });
+        }
+
         // Update the program counter.
         self.state.pc = next_pc;
 
@@ -1001,13 +2039,37 @@ impl<'a> Executor<'a> {
     #[inline]
     fn execute_cycle(&mut self) -> Result<bool, ExecutionError> {
         // Fetch the instruction at the current program counter.
-        let instruction = self.fetch();
+        let instruction = self.fetch()?;
 
         // Log the current state of the runtime.
         self.log(&instruction);
 
+        // Record the instruction in the trace ring, evicting the oldest one if it's full.
+        if self.trace_ring_capacity > 0 {
+            if self.trace_ring.len() == self.trace_ring_capacity {
+                self.trace_ring.pop_front();
+            }
+            self.trace_ring.push_back((self.state.pc, instruction));
+        }
+
+        // Fail if the state repeats within the loop detection window.
+        if self.loop_window > 0 {
+            let state = self.loop_state();
+            if self.loop_states.contains(&state) {
+                return Err(ExecutionError::InfiniteLoopDetected {
+                    pc: self.state.pc,
+                    clk: self.state.clk,
+                });
+            }
+            if self.loop_states.len() == self.loop_window {
+                self.loop_states.pop_front();
+            }
+            self.loop_states.push_back(state);
+        }
+
         // Execute the instruction.
         self.execute_instruction(&instruction)?;
+        self.check_spill()?;
 
         // Increment the clock.
         self.state.global_clk += 1;
@@ -1029,8 +2091,31 @@ impl<'a> Executor<'a> {
             }
         }
 
-        Ok(self.state.pc.wrapping_sub(self.program.pc_base)
-            >= (self.program.instructions.len() * 4) as u32)
+        // An `EBREAK` ends the execution right away, even with instructions left after it.
+        if self.break_pc.is_some() {
+            return Ok(true);
+        }
+
+        let done = self.state.pc.wrapping_sub(self.program.pc_base)
+            >= (self.program.instructions.len() * 4) as u32;
+
+        // The `HALT` syscall jumps to pc 0, anything else left the program unexpectedly.
+        if done && self.state.pc != 0 && self.fall_off_policy == FallOffPolicy::Error {
+            return Err(ExecutionError::UnexpectedTermination(self.state.pc));
+        }
+
+        Ok(done)
+    }
+
+    /// Hashes the program counter and the registers for loop detection.
+    fn loop_state(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.state.pc.hash(&mut hasher);
+        for register in 0..NUM_REGISTERS {
+            // Registers that were never accessed read as zero. They are never spilled either.
+            self.state.memory.get(&register).map_or(0, |record| record.value).hash(&mut hasher);
+        }
+        hasher.finish()
     }
 
     /// Bump the record.
@@ -1065,6 +2150,7 @@ impl<'a> Executor<'a> {
         self.touched_memory.clear();
         self.emit_events = false;
         self.print_report = false;
+        self.unspill()?;
         let mut state = self.state.clone();
         let done = self.execute()?;
         // Remove the untouched addresses from the checkpoint. Skip if `done` since we need all of
@@ -1086,14 +2172,25 @@ impl<'a> Executor<'a> {
         Ok((state, done))
     }
 
-    fn initialize(&mut self) {
+    fn initialize(&mut self) -> Result<(), ExecutionError> {
         self.state.clk = 0;
         self.state.channel = 0;
 
         tracing::debug!("loading memory image");
+        if let Some((&addr, _)) = self.program.memory_image.range(..NUM_REGISTERS).next() {
+            return Err(ExecutionError::RegisterFileAliasing {
+                addr,
+                pc: self.program.pc_start,
+                clk: 0,
+            });
+        }
         for (addr, value) in &self.program.memory_image {
+            if let Some(spill) = &mut self.memory_spill {
+                spill.access(&mut self.state.memory, *addr);
+            }
             self.state.memory.insert(*addr, MemoryRecord { value: *value, shard: 0, timestamp: 0 });
         }
+        self.check_spill()
     }
 
     /// Executes the program without tracing and without emitting events.
@@ -1108,6 +2205,21 @@ impl<'a> Executor<'a> {
         Ok(())
     }
 
+    /// Decodes `words` into a program starting at address 0, runs it and returns the finished
+    /// runtime for inspection. Running past the last word ends the execution.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a word cannot be decoded or the program execution
+    /// fails.
+    pub fn run_words(words: &[u32]) -> Result<Self, ExecutionError> {
+        let program = Program::new(decode_words_par(words)?, 0, 0);
+        let mut runtime =
+            Self::new(program, SP1CoreOpts::default()).with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.run()?;
+        Ok(runtime)
+    }
+
     /// Executes the program and prints the execution report.
     ///
     /// # Errors
@@ -1120,6 +2232,156 @@ impl<'a> Executor<'a> {
         Ok(())
     }
 
+    /// Executes the program, comparing each cpu event as it is emitted with the cpu events of
+    /// `expected`, in order, and stops at the first difference.
+    ///
+    /// The shard, clock, program counters and operands of the events are compared. The records are
+    /// otherwise the same as with [`Executor::run`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return the first [`Divergence`] from `expected`, or the error of the
+    /// program execution if it fails.
+    pub fn run_checked(&mut self, expected: &ExecutionRecord) -> Result<(), Divergence> {
+        self.emit_events = true;
+        self.print_report = true;
+
+        let mut expected_events = expected.cpu_events.iter();
+        let mut num_checked = 0;
+        // The events before `num_events` of the record at `num_records` were already checked.
+        let mut num_records = self.records.len();
+        let mut num_events = self.record.cpu_events.len();
+        let mut check = |runtime: &Self| {
+            // The record being filled moves to `records` when the cycle starts a new shard.
+            let records = runtime.records[num_records..].iter().chain([&runtime.record]);
+            for (i, record) in records.enumerate() {
+                let start = if i == 0 { num_events } else { 0 };
+                for event in &record.cpu_events[start..] {
+                    let Some(expected_event) = expected_events.next() else {
+                        return Err(Divergence::ExtraEvents(expected.cpu_events.len()));
+                    };
+                    let fields = [
+                        ("shard", expected_event.shard, event.shard),
+                        ("clk", expected_event.clk, event.clk),
+                        ("pc", expected_event.pc, event.pc),
+                        ("next_pc", expected_event.next_pc, event.next_pc),
+                        ("a", expected_event.a, event.a),
+                        ("b", expected_event.b, event.b),
+                        ("c", expected_event.c, event.c),
+                    ];
+                    if let Some((field, expected, actual)) =
+                        fields.into_iter().find(|(_, expected, actual)| expected != actual)
+                    {
+                        return Err(Divergence::Value {
+                            field,
+                            expected,
+                            actual,
+                            pc: event.pc,
+                            clk: event.clk,
+                        });
+                    }
+                    num_checked += 1;
+                }
+            }
+            num_records = runtime.records.len();
+            num_events = runtime.record.cpu_events.len();
+            Ok(())
+        };
+        while !self.execute_with(&mut check)? {}
+
+        if num_checked < expected.cpu_events.len() {
+            return Err(Divergence::MissingEvents {
+                expected: expected.cpu_events.len(),
+                actual: num_checked,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Executes a copy of the runtime to completion without emitting events, returning the
+    /// resulting [`ExecutionReport`].
+    ///
+    /// The copy doesn't carry over the hooks or the subproof verifier of this runtime.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the program execution fails.
+    pub fn count_opcodes(&self) -> Result<ExecutionReport, ExecutionError> {
+        let mut runtime =
+            Executor::recover(self.program.as_ref().clone(), self.state.clone(), self.opts);
+        runtime.max_cycles = self.max_cycles;
+        runtime.fall_off_policy = self.fall_off_policy;
+        runtime.run_untraced()?;
+        Ok(runtime.report)
+    }
+
+    /// Builds a smaller program that reproduces the execution of this runtime's program up to
+    /// global clock `target_clk`, to help isolate a faulty event.
+    ///
+    /// The program is re-executed from the start for `target_clk` cycles. The instructions of the
+    /// basic blocks (see [`Program::build_cfg`]) that were not entered are replaced by `UNIMP`, and
+    /// the trailing ones are dropped. Addresses, the entrypoint and the memory image are kept, so
+    /// running the reproducer for `target_clk` cycles reaches the same state.
+    ///
+    /// The copy doesn't carry over the hooks or the subproof verifier of this runtime.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the memory image of the program can't be loaded.
+    pub fn minimal_reproducer(&self, target_clk: u64) -> Result<Program, ExecutionError> {
+        let cfg = self.program.build_cfg();
+        let mut visited = vec![false; cfg.blocks.len()];
+
+        let mut runtime = Executor::new(self.program.as_ref().clone(), self.opts)
+            .with_fall_off_policy(self.fall_off_policy);
+        runtime.emit_events = false;
+        runtime.initialize()?;
+        while runtime.state.global_clk < target_clk {
+            if let Some(block) = cfg.block_of(runtime.state.pc) {
+                visited[block] = true;
+            }
+            if !matches!(runtime.execute_cycle(), Ok(false)) {
+                break;
+            }
+        }
+
+        let mut program = self.program.as_ref().clone();
+        let unimp = Instruction::new(Opcode::UNIMP, 0, 0, 0, true, true);
+        for (block, _) in cfg.blocks.iter().zip(&visited).filter(|(_, visited)| !**visited) {
+            let start = ((block.start - program.pc_base) / 4) as usize;
+            let end = ((block.end - program.pc_base) / 4) as usize;
+            program.instructions[start..end].fill(unimp);
+        }
+        let len = cfg
+            .blocks
+            .iter()
+            .zip(&visited)
+            .filter(|(_, visited)| **visited)
+            .map(|(block, _)| ((block.end - program.pc_base) / 4) as usize)
+            .max()
+            .unwrap_or(0);
+        program.instructions.truncate(len);
+        Ok(program)
+    }
+
+    /// Pre-sizes the CPU and ALU event vectors of the current record with the opcode counts of a
+    /// first pass over the program (see [`Executor::count_opcodes`]).
+    ///
+    /// The counts cover the rest of the execution, so they are exact when it fits in a single
+    /// shard.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the program execution fails.
+    pub fn presize_record(&mut self) -> Result<(), ExecutionError> {
+        let report = self.count_opcodes()?;
+        let mut record = ExecutionRecord::with_capacity(self.program.clone(), &report);
+        record.public_values = self.record.public_values;
+        self.record = record;
+        Ok(())
+    }
+
     /// Executes the program without emitting events.
     ///
     /// # Panics
@@ -1130,9 +2392,135 @@ impl<'a> Executor<'a> {
         while !self.execute().unwrap() {}
     }
 
+    /// Executes the program up to global clock `end_clk`, only emitting events for the cycles in
+    /// `start_clk..end_clk`.
+    ///
+    /// This is useful for producing a focused trace of a specific window of the execution (e.g.
+    /// the cycles leading up to a fault). Execution stops early if the program finishes.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the program execution fails.
+    pub fn run_range(&mut self, start_clk: u64, end_clk: u64) -> Result<(), ExecutionError> {
+        self.print_report = true;
+
+        // If it's the first cycle, initialize the program.
+        if self.state.global_clk == 0 {
+            self.initialize()?;
+        }
+
+        while self.state.global_clk < end_clk {
+            self.emit_events = self.state.global_clk >= start_clk;
+            if self.execute_cycle()? {
+                break;
+            }
+        }
+
+        // Push the remaining execution record, if there are any CPU events.
+        if !self.record.cpu_events.is_empty() {
+            self.bump_record();
+        }
+
+        Ok(())
+    }
+
+    /// Executes a single instruction, returning whether the program has finished.
+    ///
+    /// If [`Executor::undo_depth`] is set, the prior state is saved so that the instruction can be
+    /// reverted with [`Executor::undo`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the instruction fails to execute.
+    pub fn step(&mut self) -> Result<bool, ExecutionError> {
+        // If it's the first cycle, initialize the program.
+        if self.state.global_clk == 0 {
+            self.initialize()?;
+        }
+
+        if self.undo_depth == 0 {
+            return self.execute_cycle();
+        }
+
+        self.undo_step = Some(UndoState {
+            global_clk: self.state.global_clk,
+            current_shard: self.state.current_shard,
+            clk: self.state.clk,
+            channel: self.state.channel,
+            pc: self.state.pc,
+            memory_diff: HashMap::default(),
+            input_stream_ptr: self.state.input_stream_ptr,
+            public_values_stream_len: self.state.public_values_stream.len(),
+            public_values_stream_ptr: self.state.public_values_stream_ptr,
+            num_records: self.records.len(),
+            event_lengths: self.record.event_lengths(),
+            public_values: self.record.public_values,
+        });
+        let result = self.execute_cycle();
+        while self.undo_log.len() >= self.undo_depth {
+            self.undo_log.pop_front();
+        }
+        self.undo_log.extend(self.undo_step.take());
+        result
+    }
+
+    /// Reverts the last instruction executed with [`Executor::step`], returning whether there was
+    /// an instruction to revert.
+    ///
+    /// At most [`Executor::undo_depth`] instructions can be reverted in a row.
+    ///
+    /// This restores the program counter, clocks, registers, memory and the emitted events. Side
+    /// effects outside of the runtime (e.g. writes to stdout) and the execution report are not
+    /// reverted.
+    pub fn undo(&mut self) -> bool {
+        let Some(undo) = self.undo_log.pop_back() else {
+            return false;
+        };
+
+        // Restore the memory to its state before the step.
+        for (addr, record) in undo.memory_diff {
+            self.page_in(addr);
+            match record {
+                Some(record) => self.state.memory.insert(addr, record),
+                None => self.state.memory.remove(&addr),
+            };
+        }
+
+        // If the step moved to a new shard, restore the previous record.
+        if let Some(record) = self.records.split_off(undo.num_records).into_iter().next() {
+            self.record = record;
+        }
+        self.record.truncate_events(&undo.event_lengths);
+        self.record.public_values = undo.public_values;
+
+        self.state.global_clk = undo.global_clk;
+        self.state.current_shard = undo.current_shard;
+        self.state.clk = undo.clk;
+        self.state.channel = undo.channel;
+        self.state.pc = undo.pc;
+        self.state.input_stream_ptr = undo.input_stream_ptr;
+        self.state.public_values_stream.truncate(undo.public_values_stream_len);
+        self.state.public_values_stream_ptr = undo.public_values_stream_ptr;
+
+        // Undoing the `EBREAK` that stopped the execution resumes it.
+        if self.break_pc == Some(undo.pc) {
+            self.break_pc = None;
+        }
+        true
+    }
+
     /// Executes up to `self.shard_batch_size` cycles of the program, returning whether the program
     /// has finished.
     fn execute(&mut self) -> Result<bool, ExecutionError> {
+        self.execute_with(|_| Ok(()))
+    }
+
+    /// Like [`Executor::execute`], but calls `check` after every cycle and stops with its error, if
+    /// any.
+    fn execute_with<E: From<ExecutionError>>(
+        &mut self,
+        mut check: impl FnMut(&Self) -> Result<(), E>,
+    ) -> Result<bool, E> {
         // Get the program.
         let program = self.program.clone();
 
@@ -1141,7 +2529,7 @@ impl<'a> Executor<'a> {
 
         // If it's the first cycle, initialize the program.
         if self.state.global_clk == 0 {
-            self.initialize();
+            self.initialize()?;
         }
 
         // Loop until we've executed `self.shard_batch_size` shards if `self.shard_batch_size` is
@@ -1150,7 +2538,9 @@ impl<'a> Executor<'a> {
         let mut current_shard = self.state.current_shard;
         let mut num_shards_executed = 0;
         loop {
-            if self.execute_cycle()? {
+            let finished = self.execute_cycle()?;
+            check(self)?;
+            if finished {
                 done = true;
                 break;
             }
@@ -1173,6 +2563,8 @@ impl<'a> Executor<'a> {
         }
 
         if done {
+            // Load the spilled memory back, since all of it is needed for MemoryFinalize.
+            self.unspill()?;
             self.postprocess();
 
             // Push the remaining execution record with memory initialize & finalize events.
@@ -1296,6 +2688,25 @@ impl<'a> Executor<'a> {
     }
 }
 
+/// Executes `program` with `stdin` as its input, returning the bytes it committed and the final
+/// public values.
+///
+/// # Errors
+///
+/// This function will return an error if the program execution fails.
+pub fn execute_program(
+    program: Arc<Program>,
+    stdin: &[u8],
+) -> Result<(Vec<u8>, PublicValues<u32, u32>), ExecutionError> {
+    let mut runtime = Executor::new(Arc::unwrap_or_clone(program), SP1CoreOpts::default());
+    runtime.write_stdin_slice(stdin);
+    runtime.run()?;
+
+    let public_values =
+        runtime.records.last().map_or(runtime.record.public_values, |record| record.public_values);
+    Ok((std::mem::take(&mut runtime.state.public_values_stream), public_values))
+}
+
 // TODO: FIX
 /// Aligns an address to the nearest word below or equal to it.
 #[must_use]
@@ -1310,16 +2721,28 @@ pub const NUM_BYTE_LOOKUP_CHANNELS: u8 = 16;
 #[cfg(test)]
 mod tests {
 
-    use sp1_stark::SP1CoreOpts;
+    use sp1_stark::{SP1CoreOpts, SplitOpts};
 
     use crate::programs::tests::{
         fibonacci_program, panic_program, simple_memory_program, simple_program,
-        ssz_withdrawals_program,
+        ssz_withdrawals_program, FIBONACCI_ELF,
     };
 
-    use crate::Register;
+    use crate::{
+        decode,
+        disassembler::transpile,
+        events::{MemoryAccessPosition, MemoryRecordEnum},
+        syscalls::SyscallCode,
+        ChipKind, Register, SP1Context,
+    };
+
+    use std::sync::{Arc, Mutex};
 
-    use super::{Executor, Instruction, Opcode, Program};
+    use super::{
+        execute_program, Divergence, DumpFormat, Endianness, ExecutionError, Executor,
+        FallOffPolicy, Instruction, MemoryEventCounts, Opcode, Program, ProgramError,
+        UninitPattern, STACK_TOP,
+    };
 
     fn _assert_send<T: Send>() {}
 
@@ -1331,7 +2754,8 @@ mod tests {
     #[test]
     fn test_simple_program_run() {
         let program = simple_program();
-        let mut runtime = Executor::new(program, SP1CoreOpts::default());
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
         runtime.run().unwrap();
         assert_eq!(runtime.register(Register::X31), 42);
     }
@@ -1370,7 +2794,8 @@ mod tests {
             Instruction::new(Opcode::ADD, 31, 30, 29, false, false),
         ];
         let program = Program::new(instructions, 0, 0);
-        let mut runtime = Executor::new(program, SP1CoreOpts::default());
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
         runtime.run().unwrap();
         assert_eq!(runtime.register(Register::X31), 42);
     }
@@ -1387,7 +2812,8 @@ mod tests {
         ];
         let program = Program::new(instructions, 0, 0);
 
-        let mut runtime = Executor::new(program, SP1CoreOpts::default());
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
         runtime.run().unwrap();
         assert_eq!(runtime.register(Register::X31), 32);
     }
@@ -1404,7 +2830,8 @@ mod tests {
         ];
         let program = Program::new(instructions, 0, 0);
 
-        let mut runtime = Executor::new(program, SP1CoreOpts::default());
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
         runtime.run().unwrap();
         assert_eq!(runtime.register(Register::X31), 32);
     }
@@ -1421,284 +2848,1620 @@ mod tests {
         ];
         let program = Program::new(instructions, 0, 0);
 
-        let mut runtime = Executor::new(program, SP1CoreOpts::default());
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
 
         runtime.run().unwrap();
         assert_eq!(runtime.register(Register::X31), 37);
     }
 
     #[test]
-    fn test_and() {
-        //     addi x29, x0, 5
-        //     addi x30, x0, 37
-        //     and x31, x30, x29
+    fn test_and() {
+        //     addi x29, x0, 5
+        //     addi x30, x0, 37
+        //     and x31, x30, x29
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
+            Instruction::new(Opcode::ADD, 30, 0, 37, false, true),
+            Instruction::new(Opcode::AND, 31, 30, 29, false, false),
+        ];
+        let program = Program::new(instructions, 0, 0);
+
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.run().unwrap();
+        assert_eq!(runtime.register(Register::X31), 5);
+    }
+
+    #[test]
+    fn test_sll() {
+        //     addi x29, x0, 5
+        //     addi x30, x0, 37
+        //     sll x31, x30, x29
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
+            Instruction::new(Opcode::ADD, 30, 0, 37, false, true),
+            Instruction::new(Opcode::SLL, 31, 30, 29, false, false),
+        ];
+        let program = Program::new(instructions, 0, 0);
+
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.run().unwrap();
+        assert_eq!(runtime.register(Register::X31), 1184);
+    }
+
+    #[test]
+    fn test_srl() {
+        //     addi x29, x0, 5
+        //     addi x30, x0, 37
+        //     srl x31, x30, x29
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
+            Instruction::new(Opcode::ADD, 30, 0, 37, false, true),
+            Instruction::new(Opcode::SRL, 31, 30, 29, false, false),
+        ];
+        let program = Program::new(instructions, 0, 0);
+
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.run().unwrap();
+        assert_eq!(runtime.register(Register::X31), 1);
+    }
+
+    #[test]
+    fn test_sra() {
+        //     addi x29, x0, 5
+        //     addi x30, x0, 37
+        //     sra x31, x30, x29
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
+            Instruction::new(Opcode::ADD, 30, 0, 37, false, true),
+            Instruction::new(Opcode::SRA, 31, 30, 29, false, false),
+        ];
+        let program = Program::new(instructions, 0, 0);
+
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.run().unwrap();
+        assert_eq!(runtime.register(Register::X31), 1);
+    }
+
+    #[test]
+    fn test_slt() {
+        //     addi x29, x0, 5
+        //     addi x30, x0, 37
+        //     slt x31, x30, x29
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
+            Instruction::new(Opcode::ADD, 30, 0, 37, false, true),
+            Instruction::new(Opcode::SLT, 31, 30, 29, false, false),
+        ];
+        let program = Program::new(instructions, 0, 0);
+
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.run().unwrap();
+        assert_eq!(runtime.register(Register::X31), 0);
+    }
+
+    #[test]
+    fn test_sltu() {
+        //     addi x29, x0, 5
+        //     addi x30, x0, 37
+        //     sltu x31, x30, x29
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
+            Instruction::new(Opcode::ADD, 30, 0, 37, false, true),
+            Instruction::new(Opcode::SLTU, 31, 30, 29, false, false),
+        ];
+        let program = Program::new(instructions, 0, 0);
+
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.run().unwrap();
+        assert_eq!(runtime.register(Register::X31), 0);
+    }
+
+    #[test]
+    fn test_addi() {
+        //     addi x29, x0, 5
+        //     addi x30, x29, 37
+        //     addi x31, x30, 42
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
+            Instruction::new(Opcode::ADD, 30, 29, 37, false, true),
+            Instruction::new(Opcode::ADD, 31, 30, 42, false, true),
+        ];
+        let program = Program::new(instructions, 0, 0);
+
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.run().unwrap();
+        assert_eq!(runtime.register(Register::X31), 84);
+    }
+
+    #[test]
+    fn test_addi_negative() {
+        //     addi x29, x0, 5
+        //     addi x30, x29, -1
+        //     addi x31, x30, 4
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
+            Instruction::new(Opcode::ADD, 30, 29, 0xFFFF_FFFF, false, true),
+            Instruction::new(Opcode::ADD, 31, 30, 4, false, true),
+        ];
+        let program = Program::new(instructions, 0, 0);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.run().unwrap();
+        assert_eq!(runtime.register(Register::X31), 5 - 1 + 4);
+    }
+
+    #[test]
+    fn test_xori() {
+        //     addi x29, x0, 5
+        //     xori x30, x29, 37
+        //     xori x31, x30, 42
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
+            Instruction::new(Opcode::XOR, 30, 29, 37, false, true),
+            Instruction::new(Opcode::XOR, 31, 30, 42, false, true),
+        ];
+        let program = Program::new(instructions, 0, 0);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.run().unwrap();
+        assert_eq!(runtime.register(Register::X31), 10);
+    }
+
+    #[test]
+    fn test_ori() {
+        //     addi x29, x0, 5
+        //     ori x30, x29, 37
+        //     ori x31, x30, 42
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
+            Instruction::new(Opcode::OR, 30, 29, 37, false, true),
+            Instruction::new(Opcode::OR, 31, 30, 42, false, true),
+        ];
+        let program = Program::new(instructions, 0, 0);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.run().unwrap();
+        assert_eq!(runtime.register(Register::X31), 47);
+    }
+
+    #[test]
+    fn test_andi() {
+        //     addi x29, x0, 5
+        //     andi x30, x29, 37
+        //     andi x31, x30, 42
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
+            Instruction::new(Opcode::AND, 30, 29, 37, false, true),
+            Instruction::new(Opcode::AND, 31, 30, 42, false, true),
+        ];
+        let program = Program::new(instructions, 0, 0);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.run().unwrap();
+        assert_eq!(runtime.register(Register::X31), 0);
+    }
+
+    #[test]
+    fn test_slli() {
+        //     addi x29, x0, 5
+        //     slli x31, x29, 37
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
+            Instruction::new(Opcode::SLL, 31, 29, 4, false, true),
+        ];
+        let program = Program::new(instructions, 0, 0);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.run().unwrap();
+        assert_eq!(runtime.register(Register::X31), 80);
+    }
+
+    #[test]
+    fn test_srli() {
+        //    addi x29, x0, 5
+        //    srli x31, x29, 37
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 42, false, true),
+            Instruction::new(Opcode::SRL, 31, 29, 4, false, true),
+        ];
+        let program = Program::new(instructions, 0, 0);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.run().unwrap();
+        assert_eq!(runtime.register(Register::X31), 2);
+    }
+
+    #[test]
+    fn test_srai() {
+        //   addi x29, x0, 5
+        //   srai x31, x29, 37
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 42, false, true),
+            Instruction::new(Opcode::SRA, 31, 29, 4, false, true),
+        ];
+        let program = Program::new(instructions, 0, 0);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.run().unwrap();
+        assert_eq!(runtime.register(Register::X31), 2);
+    }
+
+    #[test]
+    fn test_slti() {
+        //   addi x29, x0, 5
+        //   slti x31, x29, 37
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 42, false, true),
+            Instruction::new(Opcode::SLT, 31, 29, 37, false, true),
+        ];
+        let program = Program::new(instructions, 0, 0);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.run().unwrap();
+        assert_eq!(runtime.register(Register::X31), 0);
+    }
+
+    #[test]
+    fn test_slti_negative_immediate() {
+        //   addi x29, x0, -3
+        //   slti x30, x29, -1
+        //   slti x31, x29, -5
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, neg(3), false, true),
+            Instruction::new(Opcode::SLT, 30, 29, neg(1), false, true),
+            Instruction::new(Opcode::SLT, 31, 29, neg(5), false, true),
+        ];
+        let program = Program::new(instructions, 0, 0);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.run().unwrap();
+        assert_eq!(runtime.register(Register::X30), 1);
+        assert_eq!(runtime.register(Register::X31), 0);
+    }
+
+    #[test]
+    fn test_slt_negative_register() {
+        // A negative register is less than any non-negative one, unlike with `SLTU`.
+        simple_op_code_test(Opcode::SLT, 1, neg(5), 3);
+        simple_op_code_test(Opcode::SLT, 0, 3, neg(5));
+        simple_op_code_test(Opcode::SLT, 1, neg(5), neg(3));
+        simple_op_code_test(Opcode::SLTU, 0, neg(5), 3);
+    }
+
+    #[test]
+    fn test_sltiu() {
+        //   addi x29, x0, 5
+        //   sltiu x31, x29, 37
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 42, false, true),
+            Instruction::new(Opcode::SLTU, 31, 29, 37, false, true),
+        ];
+        let program = Program::new(instructions, 0, 0);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.run().unwrap();
+        assert_eq!(runtime.register(Register::X31), 0);
+    }
+
+    #[test]
+    fn test_jal_links_next_instruction() {
+        //   0: addi x10, x0, 1
+        //   4: jal x1, 12       # call the subroutine at 16
+        //   8: addi x12, x0, 7
+        //  12: jal x0, 12       # skip over the subroutine
+        //  16: addi x10, x10, 41
+        //  20: jalr x0, x1, 0   # return to 8
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 10, 0, 1, false, true),
+            Instruction::new(Opcode::JAL, 1, 12, 0, true, true),
+            Instruction::new(Opcode::ADD, 12, 0, 7, false, true),
+            Instruction::new(Opcode::JAL, 0, 12, 0, true, true),
+            Instruction::new(Opcode::ADD, 10, 10, 41, false, true),
+            Instruction::new(Opcode::JALR, 0, 1, 0, false, true),
+        ];
+        let program = Program::new(instructions, 0, 0);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.run().unwrap();
+
+        assert_eq!(runtime.register(Register::X1), 8);
+        assert_eq!(runtime.register(Register::X10), 42);
+        assert_eq!(runtime.register(Register::X12), 7);
+        let pcs = runtime.records[0].cpu_events.iter().map(|event| event.pc).collect::<Vec<_>>();
+        assert_eq!(pcs, [0, 4, 16, 20, 8, 12]);
+    }
+
+    #[test]
+    fn test_jalr() {
+        //   addi x11, x11, 100
+        //   jalr x5, x11, 8
+        //
+        // `JALR rd offset(rs)` reads the value at rs, adds offset to it and uses it as the
+        // destination address. It then stores the address of the next instruction in rd in case
+        // we'd want to come back here.
+
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 11, 11, 100, false, true),
+            Instruction::new(Opcode::JALR, 5, 11, 8, false, true),
+        ];
+        let program = Program::new(instructions, 0, 0);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.run().unwrap();
+        assert_eq!(runtime.registers()[Register::X5 as usize], 8);
+        assert_eq!(runtime.registers()[Register::X11 as usize], 100);
+        assert_eq!(runtime.state.pc, 108);
+    }
+
+    #[test]
+    fn test_lw_memory_access_record() {
+        //   addi x29, x0, 42
+        //   sw x29, 0x100(x0)
+        //   lw x28, 0x100(x0)
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 42, false, true),
+            Instruction::new(Opcode::SW, 29, 0, 0x100, false, true),
+            Instruction::new(Opcode::LW, 28, 0, 0x100, false, true),
+        ];
+        let program = Program::new(instructions, 0, 0);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.run().unwrap();
+
+        let lw_event = runtime.records[0].cpu_events[2];
+        assert_eq!(lw_event.instruction.opcode, Opcode::LW);
+        assert_eq!(lw_event.memory_record.map(|record| record.value()), Some(42));
+        assert_eq!(lw_event.a_record.map(|record| record.value()), Some(42));
+        assert!(lw_event.b_record.is_some());
+        assert!(lw_event.c_record.is_none());
+    }
+
+    #[test]
+    fn test_run_range() {
+        let instructions = (0..20).map(|i| Instruction::new(Opcode::ADD, 29, 29, i, false, true));
+        let program = Program::new(instructions.collect(), 0, 0);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.run_range(5, 10).unwrap();
+
+        assert_eq!(runtime.state.global_clk, 10);
+        let cpu_events =
+            runtime.records.iter().flat_map(|record| record.cpu_events.iter()).collect::<Vec<_>>();
+        assert_eq!(cpu_events.len(), 5);
+        assert_eq!(cpu_events[0].pc, 20);
+        assert_eq!(cpu_events[4].pc, 36);
+        let num_add_events =
+            runtime.records.iter().map(|record| record.add_events.len()).sum::<usize>();
+        assert_eq!(num_add_events, 5);
+    }
+
+    #[test]
+    fn test_lt_events_signedness() {
+        //   addi x29, x0, 5
+        //   slt x30, x29, x0
+        //   sltu x31, x29, x0
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
+            Instruction::new(Opcode::SLT, 30, 29, 0, false, false),
+            Instruction::new(Opcode::SLTU, 31, 29, 0, false, false),
+        ];
+        let program = Program::new(instructions, 0, 0);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.run().unwrap();
+
+        let lt_events = &runtime.records[0].lt_events;
+        assert_eq!(lt_events.len(), 2);
+        assert!(lt_events[0].is_signed_comparison());
+        assert!(!lt_events[1].is_signed_comparison());
+    }
+
+    #[test]
+    fn test_set_entry() {
+        let program = simple_program();
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        assert!(runtime.set_entry(2).is_err());
+        assert!(runtime.set_entry(12).is_err());
+        runtime.set_entry(4).unwrap();
+        runtime.run().unwrap();
+
+        assert_eq!(runtime.records[0].cpu_events[0].pc, 4);
+        assert_eq!(runtime.register(Register::X29), 0);
+        assert_eq!(runtime.register(Register::X31), 37);
+    }
+
+    #[test]
+    fn test_step_undo() {
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
+            Instruction::new(Opcode::ADD, 30, 0, 0x100, false, true),
+            Instruction::new(Opcode::SW, 29, 30, 0, false, true),
+            Instruction::new(Opcode::ADD, 29, 29, 1, false, true),
+        ];
+        let program = Program::new(instructions, 0, 0);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.undo_depth = 4;
+        runtime.step().unwrap();
+        runtime.step().unwrap();
+
+        let memory = |runtime: &Executor| {
+            let mut memory = runtime
+                .state
+                .memory
+                .iter()
+                .map(|(addr, record)| (*addr, record.value, record.shard, record.timestamp))
+                .collect::<Vec<_>>();
+            memory.sort_unstable();
+            memory
+        };
+        let registers = runtime.registers();
+        let prior_memory = memory(&runtime);
+        let (pc, clk) = (runtime.state.pc, runtime.state.clk);
+        let num_cpu_events = runtime.record.cpu_events.len();
+        let num_add_events = runtime.record.add_events.len();
+
+        runtime.step().unwrap();
+        runtime.step().unwrap();
+        assert_eq!(runtime.word(0x100), 5);
+        assert_eq!(runtime.register(Register::X29), 6);
+
+        assert!(runtime.undo());
+        assert!(runtime.undo());
+        assert_eq!(runtime.registers(), registers);
+        assert_eq!(memory(&runtime), prior_memory);
+        assert_eq!((runtime.state.pc, runtime.state.clk), (pc, clk));
+        assert_eq!(runtime.record.cpu_events.len(), num_cpu_events);
+        assert_eq!(runtime.record.add_events.len(), num_add_events);
+
+        assert!(runtime.undo());
+        assert!(runtime.undo());
+        assert!(!runtime.undo());
+        assert!(runtime.record.cpu_events.is_empty());
+
+        // Only the last `undo_depth` steps can be reverted.
+        runtime.undo_depth = 1;
+        runtime.step().unwrap();
+        runtime.step().unwrap();
+        assert!(runtime.undo());
+        assert!(!runtime.undo());
+        assert_eq!(runtime.state.pc, 4);
+    }
+
+    #[test]
+    fn test_invalid_memory_access_reports_pc() {
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 0x101, false, true),
+            Instruction::new(Opcode::LW, 31, 29, 0, false, true),
+        ];
+        let program = Program::new(instructions, 0, 0);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        let err = runtime.run().unwrap_err();
+        assert!(matches!(
+            err,
+            ExecutionError::MisalignedMemoryAccess {
+                opcode: Opcode::LW,
+                addr: 0x101,
+                pc: 4,
+                clk: 4
+            }
+        ));
+        assert!(err.to_string().contains("at pc 0x4"));
+    }
+
+    #[test]
+    fn test_trace_ring() {
+        let instructions = vec![Instruction::new(Opcode::ADD, 29, 29, 1, false, true); 100];
+        let program = Program::new(instructions, 0, 0);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt)
+            .with_trace_ring(10);
+        runtime.run().unwrap();
+        assert_eq!(runtime.register(Register::X29), 100);
+
+        let recent = runtime.recent_instructions();
+        let pcs = recent.iter().map(|(pc, _)| *pc).collect::<Vec<_>>();
+        assert_eq!(pcs, (90..100).map(|i| i * 4).collect::<Vec<_>>());
+        assert!(recent.iter().all(|(_, instruction)| instruction.opcode == Opcode::ADD));
+    }
+
+    #[test]
+    fn test_mulh_sign_extension() {
+        // -1 * -1 = 1, whose upper word is 0.
+        simple_op_code_test(Opcode::MULH, 0, u32::MAX, u32::MAX);
+        // -1 * (2^32 - 1) = -(2^32 - 1), whose upper word is -1.
+        simple_op_code_test(Opcode::MULHSU, u32::MAX, u32::MAX, u32::MAX);
+        // (2^32 - 1) * (2^32 - 1) = 2^64 - 2^33 + 1, whose upper word is 2^32 - 2.
+        simple_op_code_test(Opcode::MULHU, u32::MAX - 1, u32::MAX, u32::MAX);
+    }
+
+    #[test]
+    fn test_x0_reads_zero() {
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 0, 0, 5, false, true),
+            Instruction::new(Opcode::ADD, 29, 0, 0, false, false),
+        ];
+        let program = Program::new(instructions, 0, 0);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.run().unwrap();
+        assert_eq!(runtime.register(Register::X0), 0);
+        assert_eq!(runtime.register(Register::X29), 0);
+
+        let event = &runtime.records[0].cpu_events[1];
+        assert_eq!((event.b, event.c), (0, 0));
+        assert_eq!(event.b_record.unwrap().value(), 0);
+        assert_eq!(event.c_record.unwrap().value(), 0);
+    }
+
+    #[test]
+    fn test_misaligned_branch_target() {
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
+            Instruction::new(Opcode::BEQ, 29, 29, 6, false, true),
+            Instruction::new(Opcode::ADD, 30, 0, 1, false, true),
+        ];
+        let program = Program::new(instructions, 0, 0);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        let err = runtime.run().unwrap_err();
+        assert!(matches!(err, ExecutionError::MisalignedPc { target: 10, pc: 4, clk: 4 }));
+    }
+
+    #[test]
+    fn test_jalr_clears_low_bit() {
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 12 | 1, false, true),
+            Instruction::new(Opcode::JALR, 1, 29, 0, false, true),
+            Instruction::new(Opcode::ADD, 30, 0, 1, false, true),
+            Instruction::new(Opcode::ADD, 31, 0, 1, false, true),
+        ];
+        let program = Program::new(instructions, 0, 0);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.run().unwrap();
+
+        assert_eq!(runtime.records[0].cpu_events[1].next_pc, 12);
+        assert_eq!(runtime.register(Register::X30), 0);
+        assert_eq!(runtime.register(Register::X31), 1);
+
+        // A target that is still misaligned once the low bit is cleared is rejected.
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 10 | 1, false, true),
+            Instruction::new(Opcode::JALR, 1, 29, 0, false, true),
+        ];
+        let program = Program::new(instructions, 0, 0);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        let err = runtime.run().unwrap_err();
+        assert!(matches!(err, ExecutionError::MisalignedPc { target: 10, pc: 4, clk: 4 }));
+    }
+
+    #[test]
+    fn test_execute_program() {
+        // Commits the length of the first input as a little-endian word.
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 5, 0, SyscallCode::HINT_LEN as u32, false, true),
+            Instruction::new(Opcode::ECALL, 5, 10, 11, false, false),
+            Instruction::new(Opcode::SW, 5, 0, 0x1000, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, 3, false, true),
+            Instruction::new(Opcode::ADD, 11, 0, 0x1000, false, true),
+            Instruction::new(Opcode::ADD, 12, 0, 4, false, true),
+            Instruction::new(Opcode::ADD, 5, 0, SyscallCode::WRITE as u32, false, true),
+            Instruction::new(Opcode::ECALL, 5, 10, 11, false, false),
+        ];
+        let program = Arc::new(Program::new(instructions, 0, 0));
+        let (committed, public_values) = execute_program(program, &[7; 13]).unwrap();
+        assert_eq!(committed, 13u32.to_le_bytes());
+        assert_eq!(public_values.exit_code, 0);
+    }
+
+    #[test]
+    fn test_presize_record() {
+        let program = fibonacci_program();
+        let mut runtime = Executor::new(program, SP1CoreOpts::default());
+        runtime.presize_record().unwrap();
+        let capacities = [
+            runtime.record.cpu_events.capacity(),
+            runtime.record.add_events.capacity(),
+            runtime.record.sub_events.capacity(),
+            runtime.record.mul_events.capacity(),
+            runtime.record.bitwise_events.capacity(),
+            runtime.record.shift_left_events.capacity(),
+            runtime.record.shift_right_events.capacity(),
+            runtime.record.divrem_events.capacity(),
+            runtime.record.lt_events.capacity(),
+        ];
+        runtime.run().unwrap();
+
+        let record = &runtime.records[0];
+        let lengths = [
+            record.cpu_events.len(),
+            record.add_events.len(),
+            record.sub_events.len(),
+            record.mul_events.len(),
+            record.bitwise_events.len(),
+            record.shift_left_events.len(),
+            record.shift_right_events.len(),
+            record.divrem_events.len(),
+            record.lt_events.len(),
+        ];
+        assert_eq!(capacities, lengths);
+    }
+
+    #[test]
+    fn test_immediate_bitwise_events() {
+        let i_type = |imm: u32, rs1: u32, funct3: u32, rd: u32| {
+            (imm << 20) | (rs1 << 15) | (funct3 << 12) | (rd << 7) | 0b001_0011
+        };
+        let instructions = transpile(&[
+            // addi x29, x0, 12
+            i_type(12, 0, 0b000, 29),
+            // xori x30, x29, 10
+            i_type(10, 29, 0b100, 30),
+            // ori x31, x29, 3
+            i_type(3, 29, 0b110, 31),
+            // andi x28, x29, 6
+            i_type(6, 29, 0b111, 28),
+        ]);
+        let program = Program::new(instructions, 0, 0);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.run().unwrap();
+        assert_eq!(runtime.register(Register::X30), 6);
+        assert_eq!(runtime.register(Register::X31), 15);
+        assert_eq!(runtime.register(Register::X28), 4);
+
+        let record = &runtime.records[0];
+        assert_eq!(record.add_events.len(), 1);
+        let opcodes = record.bitwise_events.iter().map(|event| event.opcode).collect::<Vec<_>>();
+        assert_eq!(opcodes, [Opcode::XOR, Opcode::OR, Opcode::AND]);
+    }
+
+    #[test]
+    fn test_memory_write_prev_value() {
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
+            Instruction::new(Opcode::SW, 29, 0, 0x100, false, true),
+            Instruction::new(Opcode::ADD, 29, 0, 7, false, true),
+            Instruction::new(Opcode::SW, 29, 0, 0x100, false, true),
+            Instruction::new(Opcode::LW, 30, 0, 0x100, false, true),
+        ];
+        let program = Program::new(instructions, 0, 0);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.run().unwrap();
+
+        let events = &runtime.records[0].cpu_events;
+        let first_write = events[1].memory_record.unwrap();
+        assert_eq!((first_write.prev_value(), first_write.value()), (0, 5));
+        let overwrite = events[3].memory_record.unwrap();
+        assert_eq!((overwrite.prev_value(), overwrite.value()), (5, 7));
+        let read = events[4].memory_record.unwrap();
+        assert_eq!((read.prev_value(), read.value()), (7, 7));
+    }
+
+    #[test]
+    fn test_minimal_reproducer() {
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 5, 0, 3, false, true),
+            Instruction::new(Opcode::ADD, 6, 0, 0, false, true),
+            // Loop: x6 += x5 while x5 != 0.
+            Instruction::new(Opcode::ADD, 6, 6, 5, false, false),
+            Instruction::new(Opcode::ADD, 5, 5, u32::MAX, false, true),
+            Instruction::new(Opcode::BNE, 5, 0, -8i32 as u32, false, true),
+            // Skip over the next instruction.
+            Instruction::new(Opcode::BEQ, 0, 0, 8, false, true),
+            Instruction::new(Opcode::ADD, 7, 0, 99, false, true),
+            Instruction::new(Opcode::ADD, 8, 6, 0, false, true),
+            Instruction::new(Opcode::ADD, 9, 0, 1, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, 2, false, true),
+        ];
+        let program = Program::new(instructions, 0, 0);
+        let target_clk = 14;
+
+        let run_to_target = |program: Program| {
+            let mut runtime = Executor::new(program, SP1CoreOpts::default())
+                .with_fall_off_policy(FallOffPolicy::Halt);
+            while runtime.state.global_clk < target_clk {
+                runtime.step().unwrap();
+            }
+            (runtime.state.pc, runtime.registers())
+        };
+
+        let runtime = Executor::new(program.clone(), SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        let reproducer = runtime.minimal_reproducer(target_clk).unwrap();
+        assert_eq!(reproducer.instructions.len(), 10);
+        assert_eq!(reproducer.instructions[6].opcode, Opcode::UNIMP);
+        assert_eq!(run_to_target(reproducer), run_to_target(program.clone()));
+
+        let reproducer = runtime.minimal_reproducer(12).unwrap();
+        assert_eq!(reproducer.instructions.len(), 6);
+    }
+
+    #[test]
+    fn test_alu_events_carry_shard() {
+        let instructions = (0..50).map(|i| Instruction::new(Opcode::ADD, 29, 29, i, false, true));
+        let program = Program::new(instructions.collect(), 0, 0);
+        let mut opts = SP1CoreOpts::default();
+        opts.shard_size = 32;
+        let mut runtime = Executor::new(program, opts).with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.run().unwrap();
+
+        let shards = runtime
+            .records
+            .iter()
+            .filter(|record| !record.cpu_events.is_empty())
+            .map(|record| {
+                let shard = record.cpu_events[0].shard;
+                assert_eq!(record.add_events.len(), record.cpu_events.len());
+                assert!(record.add_events.iter().all(|event| event.shard == shard));
+                shard
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(shards, (1..=shards.len() as u32).collect::<Vec<_>>());
+        assert!(shards.len() > 1);
+    }
+
+    #[test]
+    fn test_dump_memory() {
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 0x1234_5678, false, true),
+            Instruction::new(Opcode::SW, 29, 0, 0x104, false, true),
+            Instruction::new(Opcode::SW, 29, 0, 0x120, false, true),
+        ];
+        let program = Program::new(instructions, 0, 0);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.run().unwrap();
+
+        let mut dump = |fmt| {
+            let mut buf = Vec::new();
+            runtime.dump_memory(fmt, &mut buf).unwrap();
+            buf
+        };
+
+        let hex = String::from_utf8(dump(DumpFormat::Hex)).unwrap();
+        assert_eq!(
+            hex,
+            "00000100: 00 00 00 00 78 56 34 12 00 00 00 00 00 00 00 00\n\
+             00000120: 78 56 34 12 00 00 00 00 00 00 00 00 00 00 00 00\n"
+        );
+
+        let bin = dump(DumpFormat::Bin);
+        assert_eq!(bin, [0x104u32, 0x1234_5678, 0x120, 0x1234_5678].map(u32::to_le_bytes).concat());
+
+        let json = String::from_utf8(dump(DumpFormat::Json)).unwrap();
+        assert_eq!(json, "{\"0x104\":305419896,\"0x120\":305419896}\n");
+    }
+
+    #[test]
+    fn test_readonly_region() {
+        let program = |addr| {
+            let instructions = vec![
+                Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
+                Instruction::new(Opcode::SW, 29, 0, addr, false, true),
+            ];
+            Program::new(instructions, 0, 0)
+        };
+
+        let mut runtime = Executor::new(program(0x10c), SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.mark_readonly(0x100, 0x10);
+        let err = runtime.run().unwrap_err();
+        assert!(matches!(err, ExecutionError::ReadOnlyWrite { addr: 0x10c, pc: 4, .. }));
+
+        let mut runtime = Executor::new(program(0x110), SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.mark_readonly(0x100, 0x10);
+        runtime.run().unwrap();
+        assert_eq!(runtime.word(0x110), 5);
+    }
+
+    #[test]
+    fn test_register_file_aliasing() {
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 30, 0, 0xFFFF_FFF0, false, true),
+            Instruction::new(Opcode::SW, 29, 30, 0x18, false, true),
+        ];
+        let program = Program::new(instructions, 0, 0);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        let err = runtime.run().unwrap_err();
+        assert!(matches!(err, ExecutionError::RegisterFileAliasing { addr: 8, pc: 4, clk: 4 }));
+        assert_eq!(runtime.register(Register::X8), 0);
+
+        let mut program =
+            Program::new(vec![Instruction::new(Opcode::ADD, 29, 0, 5, false, true)], 0, 0);
+        program.memory_image.insert(4, 1);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        let err = runtime.run().unwrap_err();
+        assert!(matches!(err, ExecutionError::RegisterFileAliasing { addr: 4, pc: 0, clk: 0 }));
+    }
+
+    #[test]
+    fn test_run_checked() {
         let instructions = vec![
             Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
             Instruction::new(Opcode::ADD, 30, 0, 37, false, true),
-            Instruction::new(Opcode::AND, 31, 30, 29, false, false),
+            Instruction::new(Opcode::ADD, 31, 30, 29, false, false),
+            Instruction::new(Opcode::SUB, 31, 31, 29, false, false),
         ];
         let program = Program::new(instructions, 0, 0);
+        let mut reference = Executor::new(program.clone(), SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        reference.run().unwrap();
+        let mut expected = reference.records[0].clone();
+
+        let mut runtime = Executor::new(program.clone(), SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.run_checked(&expected).unwrap();
+        assert_eq!(runtime.records.len(), reference.records.len());
+        for (record, reference) in runtime.records.iter().zip(&reference.records) {
+            let (values, expected_values) = (record.public_values, reference.public_values);
+            assert_eq!(values.start_pc, expected_values.start_pc);
+            assert_eq!(values.next_pc, expected_values.next_pc);
+            assert_eq!(values.execution_shard, expected_values.execution_shard);
+            assert_eq!(record.memory_finalize_events.len(), reference.memory_finalize_events.len());
+        }
 
-        let mut runtime = Executor::new(program, SP1CoreOpts::default());
-        runtime.run().unwrap();
-        assert_eq!(runtime.register(Register::X31), 5);
+        expected.cpu_events[2].a = 41;
+        let mut runtime = Executor::new(program.clone(), SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        let err = runtime.run_checked(&expected).unwrap_err();
+        assert!(matches!(
+            err,
+            Divergence::Value { field: "a", expected: 41, actual: 42, pc: 8, clk: 8 }
+        ));
+        assert_eq!(runtime.state.global_clk, 3);
+
+        expected.cpu_events.truncate(2);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        let err = runtime.run_checked(&expected).unwrap_err();
+        assert!(matches!(err, Divergence::ExtraEvents(2)));
     }
 
     #[test]
-    fn test_sll() {
-        //     addi x29, x0, 5
-        //     addi x30, x0, 37
-        //     sll x31, x30, x29
+    fn test_watchpoint() {
         let instructions = vec![
             Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
-            Instruction::new(Opcode::ADD, 30, 0, 37, false, true),
-            Instruction::new(Opcode::SLL, 31, 30, 29, false, false),
+            Instruction::new(Opcode::SW, 29, 0, 0x100, false, true),
+            Instruction::new(Opcode::SW, 29, 0, 0x104, false, true),
+            Instruction::new(Opcode::ADD, 29, 29, 1, false, true),
+            Instruction::new(Opcode::SW, 29, 0, 0x100, false, true),
         ];
         let program = Program::new(instructions, 0, 0);
-
-        let mut runtime = Executor::new(program, SP1CoreOpts::default());
+        let hits = Arc::new(Mutex::new(Vec::new()));
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        let watch_hits = hits.clone();
+        runtime.add_watchpoint(
+            0x100,
+            Box::new(move |runtime, value| {
+                watch_hits.lock().unwrap().push((runtime.state.pc, value));
+            }),
+        );
         runtime.run().unwrap();
-        assert_eq!(runtime.register(Register::X31), 1184);
+        assert_eq!(*hits.lock().unwrap(), [(4, 5), (16, 6)]);
     }
 
     #[test]
-    fn test_srl() {
-        //     addi x29, x0, 5
-        //     addi x30, x0, 37
-        //     srl x31, x30, x29
+    fn test_static_opcode_mix() {
+        let mut program = simple_program();
+        program.instructions.push(Instruction::new(Opcode::SUB, 31, 31, 29, false, false));
+        let runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        let mix = runtime.static_opcode_mix();
+        // `simple_program` has two `ADDI`s and one `ADD`, which all use the `ADD` opcode.
+        assert_eq!(mix.into_iter().collect::<Vec<_>>(), [(Opcode::ADD, 3), (Opcode::SUB, 1)]);
+    }
+
+    #[test]
+    fn test_mulhsu_operand_order() {
+        // -2 * 2^31 = -2^32, whose high word is all ones.
+        simple_op_code_test(Opcode::MULHSU, 0xffffffff, neg(2), 0x80000000);
+        // Swapping the operands gives -2^31 * (2^32 - 2) = -2^63 + 2^32 instead.
+        simple_op_code_test(Opcode::MULHSU, 0x80000001, 0x80000000, neg(2));
+        // Treating both operands as signed (MULH) or unsigned (MULHU) differs as well.
+        simple_op_code_test(Opcode::MULH, 0x00000001, neg(2), 0x80000000);
+        simple_op_code_test(Opcode::MULHU, 0x7fffffff, neg(2), 0x80000000);
+    }
+
+    #[test]
+    fn test_unimplemented_opcodes_report_location() {
         let instructions = vec![
-            Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
-            Instruction::new(Opcode::ADD, 30, 0, 37, false, true),
-            Instruction::new(Opcode::SRL, 31, 30, 29, false, false),
+            Instruction::new(Opcode::ADD, 5, 0, 0xdead, false, true),
+            Instruction::new(Opcode::ECALL, 5, 10, 11, false, false),
         ];
-        let program = Program::new(instructions, 0, 0);
-
-        let mut runtime = Executor::new(program, SP1CoreOpts::default());
-        runtime.run().unwrap();
-        assert_eq!(runtime.register(Register::X31), 1);
+        let mut runtime = Executor::new(Program::new(instructions, 0, 0), SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        let err = runtime.run().unwrap_err();
+        assert!(matches!(
+            err,
+            ExecutionError::UnknownSyscall { syscall_id: 0xdead, pc: 4, clk: 4 }
+        ));
+        assert_eq!(err.to_string(), "unimplemented syscall 57005 at pc 0x4 (clk 4)");
     }
 
     #[test]
-    fn test_sra() {
-        //     addi x29, x0, 5
-        //     addi x30, x0, 37
-        //     sra x31, x30, x29
+    fn test_ebreak_stops_execution() {
         let instructions = vec![
             Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
-            Instruction::new(Opcode::ADD, 30, 0, 37, false, true),
-            Instruction::new(Opcode::SRA, 31, 30, 29, false, false),
+            Instruction::new(Opcode::EBREAK, 0, 0, 0, false, false),
+            Instruction::new(Opcode::ADD, 29, 0, 6, false, true),
+            Instruction::new(Opcode::ADD, 30, 0, 7, false, true),
         ];
-        let program = Program::new(instructions, 0, 0);
+        let mut runtime = Executor::new(Program::new(instructions, 0, 0), SP1CoreOpts::default());
+        runtime.run().unwrap();
+        assert_eq!(runtime.break_pc(), Some(4));
+        assert_eq!(runtime.state.global_clk, 2);
+        assert_eq!(runtime.register(Register::X29), 5);
+        assert_eq!(runtime.register(Register::X30), 0);
 
-        let mut runtime = Executor::new(program, SP1CoreOpts::default());
+        let mut runtime = Executor::new(simple_program(), SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
         runtime.run().unwrap();
-        assert_eq!(runtime.register(Register::X31), 1);
+        assert_eq!(runtime.break_pc(), None);
     }
 
     #[test]
-    fn test_slt() {
-        //     addi x29, x0, 5
-        //     addi x30, x0, 37
-        //     slt x31, x30, x29
+    fn test_register_accesses_emit_events() {
+        let mut runtime = Executor::new(simple_program(), SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.run().unwrap();
+        assert_eq!(runtime.register(Register::X29), 5);
+        assert_eq!(runtime.register(Register::X30), 37);
+        assert_eq!(runtime.register(Register::X31), 42);
+
+        // `add x31, x30, x29` reads x30 and x29 and writes x31, after the earlier writes of the
+        // same registers.
+        let event = &runtime.records[0].cpu_events[2];
+        let (
+            Some(MemoryRecordEnum::Write(a)),
+            Some(MemoryRecordEnum::Read(b)),
+            Some(MemoryRecordEnum::Read(c)),
+        ) = (event.a_record, event.b_record, event.c_record)
+        else {
+            panic!("expected register accesses, got {event:?}");
+        };
+        assert_eq!((a.prev_value, a.value), (0, 42));
+        assert_eq!((b.value, b.prev_timestamp), (37, 4 + MemoryAccessPosition::A as u32));
+        assert_eq!((c.value, c.prev_timestamp), (5, MemoryAccessPosition::A as u32));
+
+        // The registers are finalized along with the rest of memory.
+        let finalized = runtime.records.last().unwrap().memory_finalize_events.iter();
+        let mut registers = finalized
+            .filter(|event| (1..32).contains(&event.addr))
+            .map(|event| (event.addr, event.value))
+            .collect::<Vec<_>>();
+        registers.sort_unstable();
+        assert_eq!(registers, [(29, 5), (30, 37), (31, 42)]);
+    }
+
+    #[test]
+    fn test_validate_program() {
+        let validate = |instructions| {
+            Executor::new(Program::new(instructions, 0x1000, 0x1000), SP1CoreOpts::default())
+                .with_fall_off_policy(FallOffPolicy::Halt)
+                .validate_program()
+        };
+
         let instructions = vec![
             Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
+            Instruction::new(Opcode::BNE, 29, 0, 8, false, true),
             Instruction::new(Opcode::ADD, 30, 0, 37, false, true),
-            Instruction::new(Opcode::SLT, 31, 30, 29, false, false),
+            Instruction::new(Opcode::JAL, 1, (-12i32) as u32, 0, true, true),
         ];
-        let program = Program::new(instructions, 0, 0);
+        assert_eq!(validate(instructions.clone()), Ok(()));
+
+        let mut past_end = instructions.clone();
+        past_end[1] = Instruction::new(Opcode::BNE, 29, 0, 12, false, true);
+        assert_eq!(
+            validate(past_end),
+            Err(ProgramError::TargetOutOfBounds { pc: 0x1004, target: 0x1010 })
+        );
+
+        let mut before_start = instructions.clone();
+        before_start[3] = Instruction::new(Opcode::JAL, 1, (-16i32) as u32, 0, true, true);
+        assert_eq!(
+            validate(before_start),
+            Err(ProgramError::TargetOutOfBounds { pc: 0x100c, target: 0xffc })
+        );
+
+        let mut bad_register = instructions.clone();
+        bad_register[2] = Instruction::new(Opcode::ADD, 30, 32, 37, false, true);
+        assert_eq!(
+            validate(bad_register),
+            Err(ProgramError::InvalidRegister { pc: 0x1008, register: 32 })
+        );
+
+        let mut unimplemented = instructions;
+        unimplemented[2] = Instruction::new(Opcode::UNIMP, 0, 0, 0, true, true);
+        assert_eq!(validate(unimplemented), Err(ProgramError::Unimplemented { pc: 0x1008 }));
+    }
 
-        let mut runtime = Executor::new(program, SP1CoreOpts::default());
+    #[test]
+    fn test_auto_defer() {
+        let permute = [
+            Instruction::new(Opcode::ADD, 5, 0, SyscallCode::KECCAK_PERMUTE as u32, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, 0x1000, false, true),
+            Instruction::new(Opcode::ECALL, 5, 10, 11, false, false),
+        ];
+        let instructions = permute.iter().cycle().take(3 * permute.len()).copied().collect();
+        let thresholds = SplitOpts { keccak: 2, ..SplitOpts::new(1 << 10) };
+        let deferred = Arc::new(Mutex::new(Vec::new()));
+        let sink = deferred.clone();
+        let mut runtime = Executor::new(Program::new(instructions, 0, 0), SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt)
+            .with_auto_defer(thresholds, move |record| sink.lock().unwrap().push(record));
         runtime.run().unwrap();
-        assert_eq!(runtime.register(Register::X31), 0);
+        drop(runtime);
+
+        let deferred = Arc::try_unwrap(deferred).unwrap().into_inner().unwrap();
+        assert_eq!(deferred.len(), 1);
+        assert_eq!(deferred[0].keccak_permute_events.len(), 2);
+        assert_eq!(deferred[0].deferred_categories(), vec![(ChipKind::KeccakPermute, 2)]);
+        assert!(deferred[0].cpu_events.is_empty());
+
+        // The steps before the deferral can't be undone.
+        let instructions = permute.iter().cycle().take(3 * permute.len()).copied().collect();
+        let mut runtime = Executor::new(Program::new(instructions, 0, 0), SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt)
+            .with_auto_defer(thresholds, |_| {});
+        runtime.undo_depth = 16;
+        for _ in 0..=2 * permute.len() {
+            runtime.step().unwrap();
+        }
+        assert!(runtime.undo());
+        assert!(!runtime.undo());
     }
 
     #[test]
-    fn test_sltu() {
-        //     addi x29, x0, 5
-        //     addi x30, x0, 37
-        //     sltu x31, x30, x29
+    fn test_misaligned_spanning_access() {
         let instructions = vec![
-            Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
-            Instruction::new(Opcode::ADD, 30, 0, 37, false, true),
-            Instruction::new(Opcode::SLTU, 31, 30, 29, false, false),
+            Instruction::new(Opcode::ADD, 29, 0, 0x4433_2211, false, true),
+            Instruction::new(Opcode::SW, 29, 0, 0x1000, false, true),
+            Instruction::new(Opcode::ADD, 29, 0, 0x8877_6655, false, true),
+            Instruction::new(Opcode::SW, 29, 0, 0x1004, false, true),
+            Instruction::new(Opcode::LW, 30, 0, 0x1002, false, true),
+            Instruction::new(Opcode::SW, 30, 0, 0x1005, false, true),
         ];
         let program = Program::new(instructions, 0, 0);
 
-        let mut runtime = Executor::new(program, SP1CoreOpts::default());
-        runtime.run().unwrap();
-        assert_eq!(runtime.register(Register::X31), 0);
+        let mut runtime = Executor::new(program.clone(), SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        let err = runtime.run().unwrap_err();
+        assert!(matches!(
+            err,
+            ExecutionError::MisalignedMemoryAccess { opcode: Opcode::LW, addr: 0x1002, .. }
+        ));
+
+        // Misaligned accesses can't be proven, so they are only allowed without emitting events.
+        let mut runtime = Executor::new(program.clone(), SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.allow_misaligned = true;
+        let err = runtime.run().unwrap_err();
+        assert!(matches!(
+            err,
+            ExecutionError::UnprovableMisalignedAccess { opcode: Opcode::LW, addr: 0x1002, .. }
+        ));
+
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.allow_misaligned = true;
+        runtime.run_untraced().unwrap();
+        // The load takes the two high bytes of the first word and the two low bytes of the second.
+        assert_eq!(runtime.register(Register::X30), 0x6655_4433);
+        // The store writes the three high bytes of the second word and the low byte of the third.
+        assert_eq!(runtime.word(0x1000), 0x4433_2211);
+        assert_eq!(runtime.word(0x1004), 0x5544_3355);
+        assert_eq!(runtime.word(0x1008), 0x0000_0066);
     }
 
     #[test]
-    fn test_addi() {
-        //     addi x29, x0, 5
-        //     addi x30, x29, 37
-        //     addi x31, x30, 42
+    fn test_rewind() {
         let instructions = vec![
-            Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
-            Instruction::new(Opcode::ADD, 30, 29, 37, false, true),
-            Instruction::new(Opcode::ADD, 31, 30, 42, false, true),
+            Instruction::new(Opcode::LW, 29, 0, 0x1000, false, true),
+            Instruction::new(Opcode::ADD, 29, 29, 1, false, true),
+            Instruction::new(Opcode::SW, 29, 0, 0x1000, false, true),
+            Instruction::new(Opcode::ADD, 2, 0, STACK_TOP, false, true),
         ];
-        let program = Program::new(instructions, 0, 0);
+        let mut program = Program::new(instructions, 0, 0);
+        program.memory_image.insert(0x1000, 5);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
 
-        let mut runtime = Executor::new(program, SP1CoreOpts::default());
         runtime.run().unwrap();
-        assert_eq!(runtime.register(Register::X31), 84);
+        let registers = runtime.registers();
+        assert_eq!(registers[29], 6);
+
+        runtime.rewind();
+        assert_eq!(runtime.registers(), [0; 32]);
+        assert!(runtime.records.is_empty() && runtime.record.cpu_events.is_empty());
+
+        runtime.run().unwrap();
+        assert_eq!(runtime.registers(), registers);
+        assert_eq!(runtime.word(0x1000), 6);
+        assert_eq!(runtime.records.iter().map(|record| record.cpu_events.len()).sum::<usize>(), 4);
     }
 
     #[test]
-    fn test_addi_negative() {
-        //     addi x29, x0, 5
-        //     addi x30, x29, -1
-        //     addi x31, x30, 4
+    fn test_loop_detection() {
         let instructions = vec![
             Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
-            Instruction::new(Opcode::ADD, 30, 29, 0xFFFF_FFFF, false, true),
-            Instruction::new(Opcode::ADD, 31, 30, 4, false, true),
+            Instruction::new(Opcode::ADD, 30, 0, 1, false, true),
+            Instruction::new(Opcode::BEQ, 0, 0, (-4i32) as u32, false, true),
         ];
-        let program = Program::new(instructions, 0, 0);
-        let mut runtime = Executor::new(program, SP1CoreOpts::default());
+        let mut runtime = Executor::new(Program::new(instructions, 0, 0), SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt)
+            .with_loop_detection(16);
+        runtime.max_cycles = Some(1000);
+        let err = runtime.run().unwrap_err();
+        assert!(matches!(err, ExecutionError::InfiniteLoopDetected { pc: 8, clk: 16 }));
+        assert_eq!(runtime.state.global_clk, 4);
+
+        // A loop that makes progress through the registers isn't reported.
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 30, 0, 100, false, true),
+            Instruction::new(Opcode::ADD, 29, 29, 1, false, true),
+            Instruction::new(Opcode::BNE, 29, 30, (-4i32) as u32, false, true),
+        ];
+        let mut runtime = Executor::new(Program::new(instructions, 0, 0), SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt)
+            .with_loop_detection(16);
         runtime.run().unwrap();
-        assert_eq!(runtime.register(Register::X31), 5 - 1 + 4);
+        assert_eq!(runtime.register(Register::X29), 100);
     }
 
     #[test]
-    fn test_xori() {
-        //     addi x29, x0, 5
-        //     xori x30, x29, 37
-        //     xori x31, x30, 42
+    fn test_uninit_pattern_error() {
         let instructions = vec![
-            Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
-            Instruction::new(Opcode::XOR, 30, 29, 37, false, true),
-            Instruction::new(Opcode::XOR, 31, 30, 42, false, true),
+            Instruction::new(Opcode::ADD, 29, 0, 7, false, true),
+            Instruction::new(Opcode::SB, 29, 0, 0x1001, false, true),
+            Instruction::new(Opcode::LW, 30, 0, 0x1000, false, true),
+            Instruction::new(Opcode::LW, 31, 0, 0x2000, false, true),
+            Instruction::new(Opcode::LBU, 31, 0, 0x3003, false, true),
         ];
-        let program = Program::new(instructions, 0, 0);
-        let mut runtime = Executor::new(program, SP1CoreOpts::default());
+        let mut program = Program::new(instructions, 0, 0);
+        program.memory_image.insert(0x2000, 5);
+
+        let mut runtime = Executor::new(program.clone(), SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
         runtime.run().unwrap();
-        assert_eq!(runtime.register(Register::X31), 10);
+        assert_eq!(runtime.register(Register::X31), 0);
+
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.uninit_pattern = UninitPattern::Error;
+        let err = runtime.run().unwrap_err();
+        assert!(matches!(err, ExecutionError::UninitializedRead { addr: 0x3000, pc: 16, clk: 16 }));
+        // The written and preloaded words were read successfully.
+        assert_eq!(runtime.register(Register::X30), 0x700);
+        assert_eq!(runtime.register(Register::X31), 5);
     }
 
     #[test]
-    fn test_ori() {
-        //     addi x29, x0, 5
-        //     ori x30, x29, 37
-        //     ori x31, x30, 42
+    fn test_memory_event_counts() {
+        // Each instruction writes its destination and reads x0 or its two source registers.
+        let mut runtime = Executor::new(simple_program(), SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.run().unwrap();
+        assert_eq!(runtime.memory_event_counts(), (4, 3));
+
         let instructions = vec![
-            Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
-            Instruction::new(Opcode::OR, 30, 29, 37, false, true),
-            Instruction::new(Opcode::OR, 31, 30, 42, false, true),
+            Instruction::new(Opcode::ADD, 29, 0, 7, false, true),
+            Instruction::new(Opcode::SW, 29, 0, 0x1000, false, true),
+            Instruction::new(Opcode::LW, 30, 0, 0x1000, false, true),
         ];
         let program = Program::new(instructions, 0, 0);
-        let mut runtime = Executor::new(program, SP1CoreOpts::default());
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
         runtime.run().unwrap();
-        assert_eq!(runtime.register(Register::X31), 47);
+        assert_eq!(
+            runtime.memory_event_counts_by_kind(),
+            MemoryEventCounts {
+                register_reads: 4,
+                register_writes: 2,
+                memory_reads: 1,
+                memory_writes: 1
+            }
+        );
+        assert_eq!(runtime.memory_event_counts(), (5, 3));
     }
 
     #[test]
-    fn test_andi() {
-        //     addi x29, x0, 5
-        //     andi x30, x29, 37
-        //     andi x31, x30, 42
+    fn test_committed_bytes() {
+        // A version followed by a value, as a program would commit a struct.
+        let committed = (1u32, 0x0123_4567_89ab_cdefu64);
+        let bytes = bincode::serialize(&committed).unwrap();
         let instructions = vec![
-            Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
-            Instruction::new(Opcode::AND, 30, 29, 37, false, true),
-            Instruction::new(Opcode::AND, 31, 30, 42, false, true),
+            Instruction::new(Opcode::ADD, 5, 0, SyscallCode::WRITE as u32, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, 3, false, true),
+            Instruction::new(Opcode::ADD, 11, 0, 0x1000, false, true),
+            Instruction::new(Opcode::ADD, 12, 0, bytes.len() as u32, false, true),
+            Instruction::new(Opcode::ECALL, 5, 10, 11, false, false),
         ];
-        let program = Program::new(instructions, 0, 0);
-        let mut runtime = Executor::new(program, SP1CoreOpts::default());
+        let mut runtime = Executor::new(Program::new(instructions, 0, 0), SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.write_memory_region(0x1000, &bytes, Endianness::Little);
         runtime.run().unwrap();
-        assert_eq!(runtime.register(Register::X31), 0);
+
+        assert_eq!(runtime.committed_bytes(), bytes);
+        assert_eq!(&runtime.committed_bytes()[..4], 1u32.to_le_bytes());
+        assert_eq!(runtime.read_public_values::<(u32, u64)>(), committed);
     }
 
     #[test]
-    fn test_slli() {
-        //     addi x29, x0, 5
-        //     slli x31, x29, 37
+    fn test_read_before_write_addresses() {
         let instructions = vec![
-            Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
-            Instruction::new(Opcode::SLL, 31, 29, 4, false, true),
+            Instruction::new(Opcode::ADD, 29, 0, 7, false, true),
+            Instruction::new(Opcode::SW, 29, 0, 0x1000, false, true),
+            Instruction::new(Opcode::LW, 30, 0, 0x1000, false, true),
+            Instruction::new(Opcode::LBU, 30, 0, 0x2002, false, true),
+            Instruction::new(Opcode::SW, 29, 0, 0x2000, false, true),
+            Instruction::new(Opcode::LW, 30, 0, 0x2000, false, true),
+            Instruction::new(Opcode::LW, 30, 0, 0x3000, false, true),
+            Instruction::new(Opcode::LW, 30, 0, 0x1ffc, false, true),
         ];
-        let program = Program::new(instructions, 0, 0);
-        let mut runtime = Executor::new(program, SP1CoreOpts::default());
+        let mut program = Program::new(instructions, 0, 0);
+        program.memory_image.insert(0x3000, 5);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
         runtime.run().unwrap();
-        assert_eq!(runtime.register(Register::X31), 80);
+        assert_eq!(runtime.read_before_write_addresses(), [0x2000, 0x1ffc]);
     }
 
     #[test]
-    fn test_srli() {
-        //    addi x29, x0, 5
-        //    srli x31, x29, 37
-        let instructions = vec![
-            Instruction::new(Opcode::ADD, 29, 0, 42, false, true),
-            Instruction::new(Opcode::SRL, 31, 29, 4, false, true),
-        ];
-        let program = Program::new(instructions, 0, 0);
-        let mut runtime = Executor::new(program, SP1CoreOpts::default());
+    fn test_memory_region_endianness() {
+        let bytes = [0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09];
+        let instructions = vec![Instruction::new(Opcode::LW, 29, 0, 0x2004, false, true)];
+        let mut runtime = Executor::new(Program::new(instructions, 0, 0), SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.write_memory_region(0x1000, &bytes, Endianness::Little);
+        runtime.write_memory_region(0x2000, &bytes, Endianness::Big);
+
+        for (le, be) in [(0x1000, 0x2000), (0x1004, 0x2004), (0x1008, 0x2008)] {
+            let le = runtime.state.uninitialized_memory[&le];
+            let be = runtime.state.uninitialized_memory[&be];
+            assert_eq!(le, be.swap_bytes());
+        }
+        assert_eq!(runtime.state.uninitialized_memory[&0x2000], 0x0102_0304);
+        assert_eq!(runtime.state.uninitialized_memory[&0x2008], 0x0900_0000);
+
+        assert_eq!(runtime.read_memory_region(0x1000, 9, Endianness::Little).unwrap(), bytes);
+        assert_eq!(runtime.read_memory_region(0x2000, 9, Endianness::Big).unwrap(), bytes);
+        assert_eq!(
+            runtime.read_memory_region(0x2000, 4, Endianness::Little).unwrap(),
+            [4, 3, 2, 1]
+        );
+
+        // The program loads the words as written.
         runtime.run().unwrap();
-        assert_eq!(runtime.register(Register::X31), 2);
+        assert_eq!(runtime.register(Register::X29), 0x0506_0708);
     }
 
     #[test]
-    fn test_srai() {
-        //   addi x29, x0, 5
-        //   srai x31, x29, 37
+    fn test_branch_records_next_pc() {
         let instructions = vec![
-            Instruction::new(Opcode::ADD, 29, 0, 42, false, true),
-            Instruction::new(Opcode::SRA, 31, 29, 4, false, true),
+            Instruction::new(Opcode::ADD, 29, 0, 1, false, true),
+            // Not taken: x29 != x0.
+            Instruction::new(Opcode::BEQ, 29, 0, 8, false, true),
+            // Taken: x29 != x0, skipping the next instruction.
+            Instruction::new(Opcode::BNE, 29, 0, 8, false, true),
+            Instruction::new(Opcode::ADD, 30, 0, 1, false, true),
+            Instruction::new(Opcode::ADD, 31, 0, 1, false, true),
         ];
         let program = Program::new(instructions, 0, 0);
-        let mut runtime = Executor::new(program, SP1CoreOpts::default());
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
         runtime.run().unwrap();
-        assert_eq!(runtime.register(Register::X31), 2);
+
+        let events = &runtime.records[0].cpu_events;
+        assert_eq!((events[1].pc, events[1].next_pc), (4, 8));
+        assert_eq!((events[2].pc, events[2].next_pc), (8, 16));
+        assert_eq!(events[3].pc, 16);
+        assert_eq!(runtime.register(Register::X30), 0);
     }
 
     #[test]
-    fn test_slti() {
-        //   addi x29, x0, 5
-        //   slti x31, x29, 37
+    fn test_shift_amount_masked() {
+        // Only the low 5 bits of the shift amount are used, so shifting by 33 shifts by 1.
+        simple_op_code_test(Opcode::SLL, 0x0000_0002, 0x0000_0001, 33);
+        simple_op_code_test(Opcode::SRL, 0x4000_0000, 0x8000_0000, 33);
+        simple_op_code_test(Opcode::SRA, 0xc000_0000, 0x8000_0000, 33);
+
+        for (opcode, expected) in
+            [(Opcode::SLL, 0x0000_0002), (Opcode::SRL, 0x4000_0000), (Opcode::SRA, 0xc000_0000)]
+        {
+            let b = if opcode == Opcode::SLL { 1 } else { 0x8000_0000 };
+            let instructions = vec![
+                Instruction::new(Opcode::ADD, 2, 0, b, false, true),
+                Instruction::new(opcode, 1, 2, 33, false, true),
+            ];
+            let mut runtime =
+                Executor::new(Program::new(instructions, 0, 0), SP1CoreOpts::default())
+                    .with_fall_off_policy(FallOffPolicy::Halt);
+            runtime.run().unwrap();
+            assert_eq!(runtime.register(Register::X1), expected, "{opcode}i by 33");
+        }
+    }
+
+    #[test]
+    fn test_memory_writes() {
         let instructions = vec![
-            Instruction::new(Opcode::ADD, 29, 0, 42, false, true),
-            Instruction::new(Opcode::SLT, 31, 29, 37, false, true),
+            Instruction::new(Opcode::ADD, 29, 0, 0x1234, false, true),
+            Instruction::new(Opcode::ADD, 30, 0, 0x100, false, true),
+            Instruction::new(Opcode::SW, 29, 30, 8, false, true),
+            Instruction::new(Opcode::LW, 31, 30, 8, false, true),
+            Instruction::new(Opcode::SB, 29, 30, 1, false, true),
         ];
         let program = Program::new(instructions, 0, 0);
-        let mut runtime = Executor::new(program, SP1CoreOpts::default());
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
         runtime.run().unwrap();
-        assert_eq!(runtime.register(Register::X31), 0);
+
+        let writes = runtime.records[0].memory_writes().collect::<Vec<_>>();
+        assert_eq!(writes, vec![(8, 0x108, 0x1234), (16, 0x100, 0x3400)]);
     }
 
     #[test]
-    fn test_sltiu() {
-        //   addi x29, x0, 5
-        //   sltiu x31, x29, 37
+    fn test_check_stack() {
         let instructions = vec![
-            Instruction::new(Opcode::ADD, 29, 0, 42, false, true),
-            Instruction::new(Opcode::SLTU, 31, 29, 37, false, true),
+            Instruction::new(Opcode::ADD, 2, 0, STACK_TOP, false, true),
+            Instruction::new(Opcode::ADD, 2, 2, 4, false, true),
         ];
         let program = Program::new(instructions, 0, 0);
-        let mut runtime = Executor::new(program, SP1CoreOpts::default());
+
+        let mut runtime = Executor::new(program.clone(), SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
         runtime.run().unwrap();
-        assert_eq!(runtime.register(Register::X31), 0);
+        assert_eq!(runtime.register(Register::X2), STACK_TOP + 4);
+
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.check_stack = true;
+        let err = runtime.run().unwrap_err();
+        assert!(matches!(
+            err,
+            ExecutionError::StackOutOfBounds { sp, pc: 4, .. } if sp == STACK_TOP + 4
+        ));
     }
 
     #[test]
-    fn test_jalr() {
-        //   addi x11, x11, 100
-        //   jalr x5, x11, 8
-        //
-        // `JALR rd offset(rs)` reads the value at rs, adds offset to it and uses it as the
-        // destination address. It then stores the address of the next instruction in rd in case
-        // we'd want to come back here.
+    fn test_fetch_out_of_bounds() {
+        // A jump far past the end of the program ends the execution, so stepping again fetches
+        // outside of the program.
+        let instructions = vec![Instruction::new(Opcode::JAL, 0, 0x1000, 0, true, true)];
+        let program = Program::new(instructions.clone(), 0, 0);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        assert!(runtime.step().unwrap());
+        let err = runtime.step().unwrap_err();
+        assert!(matches!(err, ExecutionError::FetchOutOfBounds { pc: 0x1000, .. }));
+
+        // So does starting outside of the program.
+        let program = Program::new(instructions, 0x100, 0);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        let err = runtime.run().unwrap_err();
+        assert!(matches!(err, ExecutionError::FetchOutOfBounds { pc: 0x100, clk: 0 }));
+    }
+
+    #[test]
+    fn test_from_elf() {
+        let u16_at = |elf: &[u8], offset: usize| u16::from_le_bytes([elf[offset], elf[offset + 1]]);
+        let u32_at = |elf: &[u8], offset: usize| {
+            u32::from_le_bytes(elf[offset..offset + 4].try_into().unwrap())
+        };
+
+        let runtime = Executor::from_elf(FIBONACCI_ELF, SP1CoreOpts::default()).unwrap();
+        let entry = u32_at(FIBONACCI_ELF, 0x18);
+        assert_eq!(runtime.state.pc, entry);
+
+        // Find the words at the entrypoint in the program header that loads it.
+        let (phoff, phnum) = (u32_at(FIBONACCI_ELF, 0x1c) as usize, u16_at(FIBONACCI_ELF, 0x2c));
+        let (offset, vaddr) = (0..phnum as usize)
+            .map(|i| phoff + 32 * i)
+            .find(|&ph| {
+                let (vaddr, filesz) =
+                    (u32_at(FIBONACCI_ELF, ph + 8), u32_at(FIBONACCI_ELF, ph + 16));
+                u32_at(FIBONACCI_ELF, ph) == 1 && (vaddr..vaddr + filesz).contains(&entry)
+            })
+            .map(|ph| (u32_at(FIBONACCI_ELF, ph + 4), u32_at(FIBONACCI_ELF, ph + 8)))
+            .unwrap();
+        for i in 0..4 {
+            let addr = entry + 4 * i;
+            let word = u32_at(FIBONACCI_ELF, (offset + addr - vaddr) as usize);
+            assert_eq!(runtime.program.memory_image[&addr], word);
+
+            let expected = decode(word).unwrap();
+            let instruction =
+                runtime.program.instructions[((addr - runtime.program.pc_base) / 4) as usize];
+            assert_eq!(
+                (instruction.opcode, instruction.op_a, instruction.op_b, instruction.op_c),
+                (expected.opcode, expected.op_a, expected.op_b, expected.op_c)
+            );
+        }
+
+        // 64-bit and non-RISC-V ELFs are rejected.
+        let mut elf64 = FIBONACCI_ELF.to_vec();
+        elf64[4] = 2;
+        assert!(Executor::from_elf(&elf64, SP1CoreOpts::default()).is_err());
+        let mut x86 = FIBONACCI_ELF.to_vec();
+        x86[0x12..0x14].copy_from_slice(&0x3eu16.to_le_bytes());
+        assert!(Executor::from_elf(&x86, SP1CoreOpts::default()).is_err());
+    }
+
+    #[test]
+    fn test_fall_off_policy() {
+        let mut runtime = Executor::new(simple_program(), SP1CoreOpts::default());
+        let err = runtime.run().unwrap_err();
+        assert!(matches!(err, ExecutionError::UnexpectedTermination(12)));
+
+        let mut runtime = Executor::new(simple_program(), SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.run().unwrap();
 
+        let context = SP1Context::builder().fall_off_policy(FallOffPolicy::Halt).build();
+        let mut runtime = Executor::with_context(simple_program(), SP1CoreOpts::default(), context);
+        runtime.run().unwrap();
+    }
+
+    #[test]
+    fn test_fall_off_policy_halt() {
+        let mut runtime = Executor::new(fibonacci_program(), SP1CoreOpts::default());
+        runtime.run().unwrap();
+    }
+
+    #[test]
+    fn test_run_words() {
+        let words = [
+            // addi x29, x0, 5
+            0x0050_0e93,
+            // addi x30, x0, 37
+            0x0250_0f13,
+            // add x31, x30, x29
+            0x01df_0fb3,
+        ];
+        let mut runtime = Executor::run_words(&words).unwrap();
+        assert_eq!(runtime.register(Register::X31), 42);
+
+        assert!(matches!(Executor::run_words(&[0xffff_ffff]), Err(ExecutionError::Decode(_))));
+    }
+
+    fn memory_syscall_program(code: SyscallCode, a0: u32, a1: u32, a2: u32) -> Program {
         let instructions = vec![
-            Instruction::new(Opcode::ADD, 11, 11, 100, false, true),
-            Instruction::new(Opcode::JALR, 5, 11, 8, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, a0, false, true),
+            Instruction::new(Opcode::ADD, 11, 0, a1, false, true),
+            Instruction::new(Opcode::ADD, 12, 0, a2, false, true),
+            Instruction::new(Opcode::ADD, 5, 0, code as u32, false, true),
+            Instruction::new(Opcode::ECALL, 5, 10, 11, false, false),
         ];
-        let program = Program::new(instructions, 0, 0);
-        let mut runtime = Executor::new(program, SP1CoreOpts::default());
+        Program::new(instructions, 0, 0)
+    }
+
+    #[test]
+    fn test_memcpy_syscall() {
+        let mut program = memory_syscall_program(SyscallCode::MEMCPY, 0x2000, 0x1000, 256);
+        program.memory_image = (0..64).map(|i| (0x1000 + 4 * i, 0x0101_0101 * i)).collect();
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt)
+            .with_memory_syscalls();
         runtime.run().unwrap();
-        assert_eq!(runtime.registers()[Register::X5 as usize], 8);
-        assert_eq!(runtime.registers()[Register::X11 as usize], 100);
-        assert_eq!(runtime.state.pc, 108);
+
+        for i in 0..64 {
+            assert_eq!(runtime.word(0x2000 + 4 * i), 0x0101_0101 * i);
+        }
+        assert_eq!(runtime.word(0x2000 + 256), 0);
+    }
+
+    #[test]
+    fn test_memmove_syscall() {
+        // Shift 16 words up by one word, overlapping the source.
+        let mut program = memory_syscall_program(SyscallCode::MEMMOVE, 0x1004, 0x1000, 64);
+        program.memory_image = (0..16).map(|i| (0x1000 + 4 * i, i + 1)).collect();
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt)
+            .with_memory_syscalls();
+        runtime.run().unwrap();
+
+        assert_eq!(runtime.word(0x1000), 1);
+        for i in 0..16 {
+            assert_eq!(runtime.word(0x1004 + 4 * i), i + 1);
+        }
+    }
+
+    #[test]
+    fn test_memcpy_syscall_rejects_overlap() {
+        let program = memory_syscall_program(SyscallCode::MEMCPY, 0x1004, 0x1000, 64);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt)
+            .with_memory_syscalls();
+        let err = runtime.run().unwrap_err();
+        assert!(matches!(
+            err,
+            ExecutionError::OverlappingMemcpy { dst: 0x1004, src: 0x1000, len: 64, pc: 16, .. }
+        ));
+
+        // Adjacent ranges don't overlap.
+        let program = memory_syscall_program(SyscallCode::MEMCPY, 0x1040, 0x1000, 64);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt)
+            .with_memory_syscalls();
+        runtime.run().unwrap();
+    }
+
+    #[test]
+    fn test_memory_syscalls_reject_misaligned_args() {
+        let program = memory_syscall_program(SyscallCode::MEMCPY, 0x2000, 0x1002, 16);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt)
+            .with_memory_syscalls();
+        assert!(matches!(
+            runtime.run().unwrap_err(),
+            ExecutionError::MisalignedMemorySyscall {
+                syscall: SyscallCode::MEMCPY,
+                arg: 0x1002,
+                pc: 16,
+                ..
+            }
+        ));
+
+        let program = memory_syscall_program(SyscallCode::MEMMOVE, 0x1006, 0x1000, 16);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt)
+            .with_memory_syscalls();
+        assert!(matches!(
+            runtime.run().unwrap_err(),
+            ExecutionError::MisalignedMemorySyscall {
+                syscall: SyscallCode::MEMMOVE,
+                arg: 0x1006,
+                ..
+            }
+        ));
+
+        let program = memory_syscall_program(SyscallCode::MEMSET, 0x3000, 0xff, 6);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt)
+            .with_memory_syscalls();
+        assert!(matches!(
+            runtime.run().unwrap_err(),
+            ExecutionError::MisalignedMemorySyscall { syscall: SyscallCode::MEMSET, arg: 6, .. }
+        ));
+
+        // The memory syscalls aren't registered by default.
+        let program = memory_syscall_program(SyscallCode::MEMSET, 0x3000, 0xff, 16);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        assert!(matches!(runtime.run().unwrap_err(), ExecutionError::UnknownSyscall { .. }));
+    }
+
+    #[test]
+    fn test_memset_syscall() {
+        let program = memory_syscall_program(SyscallCode::MEMSET, 0x3000, 0x1ab, 16);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt)
+            .with_memory_syscalls();
+        runtime.run().unwrap();
+
+        assert_eq!(runtime.word(0x3000 - 4), 0);
+        for i in 0..4 {
+            assert_eq!(runtime.word(0x3000 + 4 * i), 0xabab_abab);
+        }
+        assert_eq!(runtime.word(0x3000 + 16), 0);
     }
 
     fn simple_op_code_test(opcode: Opcode, expected: u32, a: u32, b: u32) {
@@ -1708,7 +4471,8 @@ mod tests {
             Instruction::new(opcode, 12, 10, 11, false, false),
         ];
         let program = Program::new(instructions, 0, 0);
-        let mut runtime = Executor::new(program, SP1CoreOpts::default());
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
         runtime.run().unwrap();
         assert_eq!(runtime.registers()[Register::X12 as usize], expected);
     }
@@ -1886,7 +4650,8 @@ mod tests {
     #[allow(clippy::unreadable_literal)]
     fn test_simple_memory_program_run() {
         let program = simple_memory_program();
-        let mut runtime = Executor::new(program, SP1CoreOpts::default());
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
         runtime.run().unwrap();
 
         // Assert SW & LW case
@@ -104,6 +104,30 @@ impl Instruction {
     }
 }
 
+/// Sign-extends the low `bits` bits of `value` to 32 bits.
+///
+/// Immediates are encoded with widths that depend on the instruction format: 12 bits for I-type
+/// and S-type, 13 bits for B-type and 21 bits for J-type, with the sign in the highest bit.
+///
+/// # Panics
+///
+/// This function will panic if `bits` is zero or greater than 32.
+#[must_use]
+pub const fn sign_extend(value: u32, bits: u32) -> u32 {
+    debug_assert!(bits > 0 && bits <= 32, "the width to sign-extend must be between 1 and 32 bits");
+    let shift = 32 - bits;
+    (((value << shift) as i32) >> shift) as u32
+}
+
+/// Interprets `value` as a two's complement signed integer.
+///
+/// Registers and immediates are stored as `u32`, with immediates already sign-extended by the
+/// decoder, so this is all the signed instructions need to compare or shift their operands.
+#[must_use]
+pub const fn as_signed(value: u32) -> i32 {
+    value as i32
+}
+
 impl Debug for Instruction {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mnemonic = self.opcode.mnemonic();
@@ -123,3 +147,38 @@ impl Debug for Instruction {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{as_signed, sign_extend};
+
+    #[test]
+    fn test_sign_extend() {
+        assert_eq!(sign_extend(0x800, 12), 0xffff_f800);
+        assert_eq!(sign_extend(0x7ff, 12), 0x7ff);
+        assert_eq!(sign_extend(0xfff, 12), 0xffff_ffff);
+        assert_eq!(sign_extend(0x1000, 13), 0xffff_f000);
+        assert_eq!(sign_extend(0x0ffe, 13), 0x0ffe);
+        assert_eq!(sign_extend(0x10_0000, 21), 0xfff0_0000);
+        assert_eq!(sign_extend(0x0f_fffe, 21), 0x0f_fffe);
+        // Bits above the immediate's width are ignored.
+        assert_eq!(sign_extend(0xffff_f7ff, 12), 0x7ff);
+        assert_eq!(sign_extend(0x8000_0000, 32), 0x8000_0000);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "the width to sign-extend must be between 1 and 32 bits")]
+    fn test_sign_extend_zero_bits() {
+        let _ = sign_extend(0x800, 0);
+    }
+
+    #[test]
+    fn test_as_signed() {
+        assert_eq!(as_signed(0), 0);
+        assert_eq!(as_signed(0x7fff_ffff), i32::MAX);
+        assert_eq!(as_signed(0x8000_0000), i32::MIN);
+        assert_eq!(as_signed(0xffff_ffff), -1);
+        assert_eq!(as_signed(sign_extend(0xffb, 12)), -5);
+    }
+}
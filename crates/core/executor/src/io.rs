@@ -41,6 +41,15 @@ impl<'a> Executor<'a> {
         self.state.proof_stream.push((proof, vk));
     }
 
+    /// Get the bytes committed by the program so far, in the order they were committed.
+    ///
+    /// These are the raw bytes whose hash is the committed value digest, so they can be parsed
+    /// with whatever layout the program committed them in.
+    #[must_use]
+    pub fn committed_bytes(&self) -> &[u8] {
+        &self.state.public_values_stream
+    }
+
     /// Read a serializable public values from the public values stream.
     pub fn read_public_values<T: DeserializeOwned>(&mut self) -> T {
         let result = bincode::deserialize_from::<_, T>(self);
@@ -33,11 +33,13 @@ pub mod programs;
 mod record;
 mod register;
 mod report;
+mod spill;
 mod state;
 pub mod subproof;
 pub mod syscalls;
 
 pub use context::*;
+pub use disassembler::{decode, decode_words_par, DecodeError};
 pub use executor::*;
 pub use hook::*;
 pub use instruction::*;
@@ -46,3 +48,4 @@ pub use program::*;
 pub use record::*;
 pub use register::*;
 pub use report::*;
+pub use spill::*;
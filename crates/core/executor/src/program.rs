@@ -1,6 +1,11 @@
 //! Programs that can be executed by the SP1 zkVM.
 
-use std::{collections::BTreeMap, fs::File, io::Read};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet},
+    fs::File,
+    io::Read,
+};
 
 use p3_field::Field;
 use serde::{Deserialize, Serialize};
@@ -9,6 +14,7 @@ use sp1_stark::air::MachineProgram;
 use crate::{
     disassembler::{transpile, Elf},
     instruction::Instruction,
+    Opcode,
 };
 
 /// A program that can be executed by the SP1 zkVM.
@@ -65,6 +71,105 @@ impl Program {
         File::open(path)?.read_to_end(&mut elf_code)?;
         Program::from(&elf_code)
     }
+
+    /// Build the control-flow graph of the program from the static targets of its branches and
+    /// jumps.
+    ///
+    /// Indirect jumps (`JALR`) end their block without any outgoing edges.
+    #[must_use]
+    pub fn build_cfg(&self) -> Cfg {
+        let mut cfg = Cfg::default();
+        if self.instructions.is_empty() {
+            return cfg;
+        }
+
+        let pc_end = self.pc_base + 4 * self.instructions.len() as u32;
+        let is_code = |pc: u32| pc >= self.pc_base && pc < pc_end && pc % 4 == 0;
+        let pcs = (self.pc_base..pc_end).step_by(4);
+
+        // Every block starts at the entrypoint, the target of a branch or jump, or the instruction
+        // after a branch or jump.
+        let mut leaders = BTreeSet::from([self.pc_base]);
+        if is_code(self.pc_start) {
+            leaders.insert(self.pc_start);
+        }
+        for (pc, instruction) in pcs.zip(&self.instructions) {
+            if instruction.is_branch_instruction() || instruction.is_jump_instruction() {
+                let (target, _) = Self::successors(pc, instruction);
+                leaders.extend(target.into_iter().chain([pc + 4]).filter(|pc| is_code(*pc)));
+            }
+        }
+
+        cfg.blocks = leaders
+            .iter()
+            .zip(leaders.iter().skip(1).chain([&pc_end]))
+            .map(|(&start, &end)| BasicBlock { start, end })
+            .collect();
+
+        for (i, block) in cfg.blocks.iter().enumerate() {
+            let last_pc = block.end - 4;
+            let instruction = &self.instructions[((last_pc - self.pc_base) / 4) as usize];
+            let (target, falls_through) = Self::successors(last_pc, instruction);
+            let successors = target.into_iter().chain(falls_through.then_some(block.end));
+            for successor in successors {
+                if let Some(j) = cfg.block_of(successor) {
+                    cfg.edges.push((i, j));
+                }
+            }
+        }
+
+        cfg
+    }
+
+    /// Returns the static target of the instruction at `pc` and whether it may fall through to the
+    /// next instruction.
+    pub(crate) fn successors(pc: u32, instruction: &Instruction) -> (Option<u32>, bool) {
+        match instruction.opcode {
+            Opcode::BEQ | Opcode::BNE | Opcode::BLT | Opcode::BGE | Opcode::BLTU | Opcode::BGEU => {
+                (Some(pc.wrapping_add(instruction.op_c)), true)
+            }
+            Opcode::JAL => (Some(pc.wrapping_add(instruction.op_b)), false),
+            Opcode::JALR => (None, false),
+            _ => (None, true),
+        }
+    }
+}
+
+/// A sequence of instructions that is only entered at its first instruction and only left after
+/// its last instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BasicBlock {
+    /// The address of the first instruction of the block.
+    pub start: u32,
+    /// The address after the last instruction of the block.
+    pub end: u32,
+}
+
+/// The control-flow graph of a [`Program`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Cfg {
+    /// The basic blocks of the program, ordered by address.
+    pub blocks: Vec<BasicBlock>,
+    /// The edges between blocks, as pairs of indices into `blocks`.
+    pub edges: Vec<(usize, usize)>,
+}
+
+impl Cfg {
+    /// Returns the index of the block containing `pc`, if any.
+    #[must_use]
+    pub fn block_of(&self, pc: u32) -> Option<usize> {
+        self.blocks
+            .binary_search_by(|block| {
+                if block.end <= pc {
+                    Ordering::Less
+                } else if block.start > pc {
+                    Ordering::Greater
+                } else {
+                    Ordering::Equal
+                }
+            })
+            .ok()
+    }
 }
 
 impl<F: Field> MachineProgram<F> for Program {
@@ -72,3 +177,22 @@ impl<F: Field> MachineProgram<F> for Program {
         F::from_canonical_u32(self.pc_start)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{BasicBlock, Program};
+    use crate::{Instruction, Opcode};
+
+    #[test]
+    fn test_build_cfg() {
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 29, 1, false, true),
+            Instruction::new(Opcode::BNE, 29, 30, -4i32 as u32, false, true),
+            Instruction::new(Opcode::ADD, 31, 0, 1, false, true),
+        ];
+        let program = Program::new(instructions, 0, 0);
+        let cfg = program.build_cfg();
+        assert_eq!(cfg.blocks, [BasicBlock { start: 0, end: 8 }, BasicBlock { start: 8, end: 12 }]);
+        assert_eq!(cfg.edges, [(0, 0), (0, 1)]);
+    }
+}
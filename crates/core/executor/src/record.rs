@@ -1,8 +1,15 @@
-use hashbrown::HashMap;
+use hashbrown::{HashMap, HashSet};
 use itertools::{EitherOrBoth, Itertools};
 use p3_field::AbstractField;
+use rand::Rng;
 use sp1_stark::{air::PublicValues, MachineRecord, SP1CoreOpts, SplitOpts};
-use std::sync::Arc;
+use std::{
+    collections::BTreeSet,
+    io::{self, Write},
+    ops::Range,
+    sync::Arc,
+};
+use thiserror::Error;
 
 use serde::{Deserialize, Serialize};
 
@@ -13,7 +20,99 @@ use crate::events::{
     ShaCompressEvent, ShaExtendEvent, Uint256MulEvent,
 };
 
-use super::{Opcode, Program};
+use super::{align, syscalls::SyscallCode, ExecutionReport, Opcode, Program};
+
+/// Invokes `$macro!($record, <events>...)` with every event vector of an [`ExecutionRecord`].
+macro_rules! for_each_event_vec {
+    ($macro:ident, $record:ident) => {
+        $macro!(
+            $record,
+            cpu_events,
+            add_events,
+            mul_events,
+            sub_events,
+            bitwise_events,
+            shift_left_events,
+            shift_right_events,
+            divrem_events,
+            lt_events,
+            sha_extend_events,
+            sha_compress_events,
+            keccak_permute_events,
+            ed_add_events,
+            ed_decompress_events,
+            secp256k1_add_events,
+            secp256k1_double_events,
+            bn254_add_events,
+            bn254_double_events,
+            k256_decompress_events,
+            bls12381_add_events,
+            bls12381_double_events,
+            uint256_mul_events,
+            memory_initialize_events,
+            memory_finalize_events,
+            bls12381_decompress_events
+        )
+    };
+}
+
+/// Invokes `$macro!(<events>, <threshold>)` with every event vector that [`ExecutionRecord::split`]
+/// spreads over shards, along with the [`SplitOpts`] field holding its shard size.
+macro_rules! for_each_split_event_vec {
+    ($macro:ident) => {
+        $macro!(keccak_permute_events, keccak);
+        $macro!(secp256k1_add_events, deferred);
+        $macro!(secp256k1_double_events, deferred);
+        $macro!(bn254_add_events, deferred);
+        $macro!(bn254_double_events, deferred);
+        $macro!(bls12381_add_events, deferred);
+        $macro!(bls12381_double_events, deferred);
+        $macro!(sha_extend_events, sha_extend);
+        $macro!(sha_compress_events, sha_compress);
+        $macro!(ed_add_events, deferred);
+        $macro!(ed_decompress_events, deferred);
+        $macro!(k256_decompress_events, deferred);
+        $macro!(uint256_mul_events, deferred);
+        $macro!(bls12381_decompress_events, deferred);
+    };
+}
+
+/// Numbers `records` as consecutive shards starting at `first`.
+///
+/// Shard numbers end up as field elements, so every shard number must be below `modulus`, the
+/// modulus of the field. Otherwise no record is numbered.
+///
+/// # Errors
+///
+/// Returns [`SplitError::ShardOverflow`] if the last shard number would reach `modulus`.
+pub fn assign_shard_numbers(
+    records: &mut [ExecutionRecord],
+    first: u32,
+    modulus: u32,
+) -> Result<(), SplitError> {
+    let last = u64::from(first) + records.len().saturating_sub(1) as u64;
+    if !records.is_empty() && last >= u64::from(modulus) {
+        return Err(SplitError::ShardOverflow { shard: last, modulus });
+    }
+    for (shard, record) in (first..).zip(records) {
+        record.public_values.shard = shard;
+    }
+    Ok(())
+}
+
+/// Merges `shards` back into a single record, appending the events and byte lookups of each shard
+/// in order, e.g. to inspect the shards returned by [`ExecutionRecord::split`] together.
+///
+/// The program, public values and nonces are the ones of the first shard.
+#[must_use]
+pub fn merge_shards(shards: Vec<ExecutionRecord>) -> ExecutionRecord {
+    let mut shards = shards.into_iter();
+    let mut merged = shards.next().unwrap_or_default();
+    for mut shard in shards {
+        merged.append(&mut shard);
+    }
+    merged
+}
 
 /// A record of the execution of a program.
 ///
@@ -87,6 +186,39 @@ impl ExecutionRecord {
         Self { program, ..Default::default() }
     }
 
+    /// Create a new [`ExecutionRecord`] whose CPU and ALU event vectors are pre-sized for the
+    /// opcode counts of `report`, e.g. as produced by [`crate::Executor::count_opcodes`].
+    #[must_use]
+    pub fn with_capacity(program: Arc<Program>, report: &ExecutionReport) -> Self {
+        let count = |opcodes: &[Opcode]| {
+            opcodes.iter().filter_map(|opcode| report.opcode_counts.get(opcode)).sum::<u64>()
+                as usize
+        };
+        Self {
+            program,
+            cpu_events: Vec::with_capacity(report.total_instruction_count() as usize),
+            add_events: Vec::with_capacity(count(&[Opcode::ADD])),
+            mul_events: Vec::with_capacity(count(&[
+                Opcode::MUL,
+                Opcode::MULH,
+                Opcode::MULHU,
+                Opcode::MULHSU,
+            ])),
+            sub_events: Vec::with_capacity(count(&[Opcode::SUB])),
+            bitwise_events: Vec::with_capacity(count(&[Opcode::XOR, Opcode::OR, Opcode::AND])),
+            shift_left_events: Vec::with_capacity(count(&[Opcode::SLL])),
+            shift_right_events: Vec::with_capacity(count(&[Opcode::SRL, Opcode::SRA])),
+            divrem_events: Vec::with_capacity(count(&[
+                Opcode::DIV,
+                Opcode::DIVU,
+                Opcode::REM,
+                Opcode::REMU,
+            ])),
+            lt_events: Vec::with_capacity(count(&[Opcode::SLT, Opcode::SLTU])),
+            ..Default::default()
+        }
+    }
+
     /// Add a mul event to the execution record.
     pub fn add_mul_event(&mut self, mul_event: AluEvent) {
         self.mul_events.push(mul_event);
@@ -129,10 +261,361 @@ impl ExecutionRecord {
         }
     }
 
+    /// Returns the ALU events of the record grouped by their shard and opcode, so that trace
+    /// generation can process each group without filtering the event vectors again.
+    ///
+    /// Within a group, the events are in the order they were recorded.
+    #[must_use]
+    pub fn grouped_alu_events(&self) -> HashMap<(u32, Opcode), Vec<&AluEvent>> {
+        let mut groups: HashMap<(u32, Opcode), Vec<&AluEvent>> = HashMap::new();
+        let alu_events = [
+            &self.add_events,
+            &self.sub_events,
+            &self.mul_events,
+            &self.bitwise_events,
+            &self.shift_left_events,
+            &self.shift_right_events,
+            &self.divrem_events,
+            &self.lt_events,
+        ];
+        for event in alu_events.into_iter().flatten() {
+            groups.entry((event.shard, event.opcode)).or_default().push(event);
+        }
+        groups
+    }
+
+    /// Returns the ALU events of the record in execution order, i.e. sorted by `(shard, clk)`.
+    ///
+    /// Events emitted by the same instruction (e.g. the helper events of `DIV`) share a `clk`, and
+    /// are ordered by event vector rather than by emission.
+    #[must_use]
+    pub fn alu_events_in_order(&self) -> Vec<&AluEvent> {
+        let mut events = [
+            &self.add_events,
+            &self.sub_events,
+            &self.mul_events,
+            &self.bitwise_events,
+            &self.shift_left_events,
+            &self.shift_right_events,
+            &self.divrem_events,
+            &self.lt_events,
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+        events.sort_by_key(|event| (event.shard, event.clk));
+        events
+    }
+
+    /// Writes the ALU events of the record as CSV rows of `clk,opcode,a,b,c`, preceded by a header,
+    /// in the order of [`ExecutionRecord::alu_events_in_order`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if writing to `w` fails.
+    pub fn alu_events_to_csv(&self, mut w: impl Write) -> io::Result<()> {
+        writeln!(w, "clk,opcode,a,b,c")?;
+        for event in self.alu_events_in_order() {
+            writeln!(w, "{},{},{},{},{}", event.clk, event.opcode, event.a, event.b, event.c)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the number of ALU events whose second operand makes them an identity or annihilator,
+    /// such as `add x, 0`, `mul x, 1` or `and x, 0`, as a measure of the cycles a smarter compiler
+    /// could save.
+    ///
+    /// Only the second operand `c` is considered, so `add rd, x0, imm` (`li`) is not counted.
+    #[must_use]
+    pub fn trivial_alu_count(&self) -> usize {
+        [
+            &self.add_events,
+            &self.sub_events,
+            &self.mul_events,
+            &self.bitwise_events,
+            &self.shift_left_events,
+            &self.shift_right_events,
+            &self.divrem_events,
+            &self.lt_events,
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|event| match event.opcode {
+            Opcode::ADD
+            | Opcode::SUB
+            | Opcode::XOR
+            | Opcode::MULH
+            | Opcode::MULHU
+            | Opcode::MULHSU
+            | Opcode::SLTU => event.c == 0,
+            Opcode::MUL => event.c <= 1,
+            Opcode::AND | Opcode::OR => event.c == 0 || event.c == u32::MAX,
+            Opcode::SLL | Opcode::SRL | Opcode::SRA => event.c % 32 == 0,
+            Opcode::DIV | Opcode::DIVU | Opcode::REM | Opcode::REMU => event.c == 1,
+            _ => false,
+        })
+        .count()
+    }
+
+    /// Returns the sorted, distinct shard numbers referenced by the byte lookups.
+    #[must_use]
+    pub fn byte_lookup_shards(&self) -> Vec<u32> {
+        self.byte_lookups.keys().copied().sorted().collect()
+    }
+
+    /// Compacts `byte_lookups` after event generation.
+    ///
+    /// Lookups with a zero count are dropped, and lookups filed under a shard other than their own
+    /// are merged into the entry of their shard. The maps are rebuilt, so any spare capacity left
+    /// over from generation is released.
+    pub fn dedup_byte_lookups(&mut self) {
+        let byte_lookups = std::mem::take(&mut self.byte_lookups);
+        for (event, count) in byte_lookups.into_values().flatten() {
+            if count > 0 {
+                *self.byte_lookups.entry(event.shard).or_default().entry(event).or_default() +=
+                    count;
+            }
+        }
+    }
+
+    /// Returns the number of events of `chip` in this record.
+    #[must_use]
+    pub fn num_events(&self, chip: ChipKind) -> usize {
+        match chip {
+            ChipKind::Cpu => self.cpu_events.len(),
+            ChipKind::AddSub => self.add_events.len() + self.sub_events.len(),
+            ChipKind::Mul => self.mul_events.len(),
+            ChipKind::Bitwise => self.bitwise_events.len(),
+            ChipKind::ShiftLeft => self.shift_left_events.len(),
+            ChipKind::ShiftRight => self.shift_right_events.len(),
+            ChipKind::DivRem => self.divrem_events.len(),
+            ChipKind::Lt => self.lt_events.len(),
+            ChipKind::ShaExtend => self.sha_extend_events.len(),
+            ChipKind::ShaCompress => self.sha_compress_events.len(),
+            ChipKind::KeccakPermute => self.keccak_permute_events.len(),
+            ChipKind::EdAddAssign => self.ed_add_events.len(),
+            ChipKind::EdDecompress => self.ed_decompress_events.len(),
+            ChipKind::Secp256k1AddAssign => self.secp256k1_add_events.len(),
+            ChipKind::Secp256k1DoubleAssign => self.secp256k1_double_events.len(),
+            ChipKind::Secp256k1Decompress => self.k256_decompress_events.len(),
+            ChipKind::Bn254AddAssign => self.bn254_add_events.len(),
+            ChipKind::Bn254DoubleAssign => self.bn254_double_events.len(),
+            ChipKind::Bls12381AddAssign => self.bls12381_add_events.len(),
+            ChipKind::Bls12381DoubleAssign => self.bls12381_double_events.len(),
+            ChipKind::Bls12381Decompress => self.bls12381_decompress_events.len(),
+            ChipKind::Uint256MulMod => self.uint256_mul_events.len(),
+            ChipKind::MemoryInit => self.memory_initialize_events.len(),
+            ChipKind::MemoryFinalize => self.memory_finalize_events.len(),
+        }
+    }
+
+    /// Returns the number of events of each [`ChipKind`] in this record, in the order of
+    /// [`ChipKind`].
+    ///
+    /// The preprocessed `Program`, `MemoryProgram` and `Byte` chips are not included.
+    #[must_use]
+    pub fn chip_events(&self) -> [(ChipKind, usize); 24] {
+        ChipKind::ALL.map(|chip| (chip, self.num_events(chip)))
+    }
+
+    /// Returns the chips that have events to prove in this record.
+    ///
+    /// Like [`Self::chip_events`], the preprocessed `Program`, `MemoryProgram` and `Byte` chips are
+    /// not included.
+    #[must_use]
+    pub fn required_chips(&self) -> BTreeSet<ChipKind> {
+        self.chip_events()
+            .into_iter()
+            .filter(|(_, num_events)| *num_events > 0)
+            .map(|(chip, _)| chip)
+            .collect()
+    }
+
+    /// Returns the precompile chips that have events in this record, along with their number of
+    /// events, ordered by [`ChipKind`].
+    ///
+    /// This is meant for the record returned by [`Self::defer`], to route it without inspecting
+    /// every event vector. Memory events are not included.
+    #[must_use]
+    pub fn deferred_categories(&self) -> Vec<(ChipKind, usize)> {
+        self.chip_events()
+            .into_iter()
+            .filter(|(chip, num_events)| chip.is_precompile() && *num_events > 0)
+            .collect()
+    }
+
+    /// Returns the `(clk, addr, value)` of every memory write performed by a store instruction, in
+    /// the order of the cpu events.
+    ///
+    /// `addr` is the word-aligned address and `value` the full word after the write. Register
+    /// writes, and writes made by syscalls, are not included.
+    pub fn memory_writes(&self) -> impl Iterator<Item = (u32, u32, u32)> + '_ {
+        self.cpu_events.iter().filter_map(|event| match event.memory_record {
+            Some(MemoryRecordEnum::Write(record)) => {
+                let addr = align(event.b.wrapping_add(event.c));
+                // The register file occupies the addresses below 32.
+                (addr >= 32).then_some((event.clk, addr, record.value))
+            }
+            _ => None,
+        })
+    }
+
+    /// Returns the [`RecordSummary`] of the record: the ranges of clocks, shards and memory
+    /// addresses it spans, and its total number of events.
+    #[must_use]
+    pub fn summarize(&self) -> RecordSummary {
+        macro_rules! num_events {
+            ($self:ident, $($events:ident),*) => {
+                0 $(+ $self.$events.len())*
+            };
+        }
+        let addrs = self.memory_initialize_events.iter().chain(&self.memory_finalize_events);
+        RecordSummary {
+            clk: self.cpu_events.iter().map(|event| event.clk).minmax().into_option(),
+            shard: self.cpu_events.iter().map(|event| event.shard).minmax().into_option(),
+            addr: addrs.map(|event| event.addr).minmax().into_option(),
+            num_events: for_each_event_vec!(num_events, self),
+        }
+    }
+
+    /// Returns the public values of the record continuing from `prev`, the public values of the
+    /// previous shard.
+    ///
+    /// The shard starts at `prev.next_pc`, and its previous init and finalize addresses are the last
+    /// ones of `prev`. Its last init and finalize addresses are those of its last memory events, or
+    /// the previous ones if it has none. The other public values are the record's own.
+    #[must_use]
+    pub fn begin_shard(&self, prev: &PublicValues<u32, u32>) -> PublicValues<u32, u32> {
+        let addr_bits =
+            |event: &MemoryInitializeFinalizeEvent| core::array::from_fn(|i| (event.addr >> i) & 1);
+        PublicValues {
+            start_pc: prev.next_pc,
+            previous_init_addr_bits: prev.last_init_addr_bits,
+            last_init_addr_bits: self
+                .memory_initialize_events
+                .last()
+                .map_or(prev.last_init_addr_bits, addr_bits),
+            previous_finalize_addr_bits: prev.last_finalize_addr_bits,
+            last_finalize_addr_bits: self
+                .memory_finalize_events
+                .last()
+                .map_or(prev.last_finalize_addr_bits, addr_bits),
+            ..self.public_values
+        }
+    }
+
+    /// Returns the [`PublicSummary`] of the record, which is all a light client needs to check the
+    /// execution against a commitment.
+    #[must_use]
+    pub fn public_summary(&self) -> PublicSummary {
+        let public_values = self.public_values;
+        let mut committed_value_digest = [0; 32];
+        for (bytes, word) in
+            committed_value_digest.chunks_exact_mut(4).zip(public_values.committed_value_digest)
+        {
+            bytes.copy_from_slice(&word.to_le_bytes());
+        }
+        PublicSummary {
+            public_values,
+            committed_value_digest,
+            deferred_proofs_digest: public_values.deferred_proofs_digest,
+        }
+    }
+
+    /// Returns the number of distinct memory addresses that are initialized in this record.
+    #[must_use]
+    pub fn distinct_memory_addresses(&self) -> usize {
+        self.memory_initialize_events.iter().map(|event| event.addr).unique().count()
+    }
+
+    /// Returns the number of events in each category of the [`ExecutionRecord`].
+    #[must_use]
+    pub fn typed_stats(&self) -> RecordStats {
+        RecordStats {
+            cpu_events: self.cpu_events.len(),
+            add_events: self.add_events.len(),
+            mul_events: self.mul_events.len(),
+            sub_events: self.sub_events.len(),
+            bitwise_events: self.bitwise_events.len(),
+            shift_left_events: self.shift_left_events.len(),
+            shift_right_events: self.shift_right_events.len(),
+            divrem_events: self.divrem_events.len(),
+            lt_events: self.lt_events.len(),
+            sha_extend_events: self.sha_extend_events.len(),
+            sha_compress_events: self.sha_compress_events.len(),
+            keccak_permute_events: self.keccak_permute_events.len(),
+            ed_add_events: self.ed_add_events.len(),
+            ed_decompress_events: self.ed_decompress_events.len(),
+            secp256k1_add_events: self.secp256k1_add_events.len(),
+            secp256k1_double_events: self.secp256k1_double_events.len(),
+            bn254_add_events: self.bn254_add_events.len(),
+            bn254_double_events: self.bn254_double_events.len(),
+            k256_decompress_events: self.k256_decompress_events.len(),
+            bls12381_add_events: self.bls12381_add_events.len(),
+            bls12381_double_events: self.bls12381_double_events.len(),
+            uint256_mul_events: self.uint256_mul_events.len(),
+            bls12381_decompress_events: self.bls12381_decompress_events.len(),
+            memory_initialize_events: self.memory_initialize_events.len(),
+            memory_finalize_events: self.memory_finalize_events.len(),
+            byte_lookups: self
+                .cpu_events
+                .first()
+                .and_then(|event| self.byte_lookups.get(&event.shard))
+                .map_or(0, hashbrown::HashMap::len),
+        }
+    }
+
+    /// Returns the length of every event vector, in the order used by [`Self::truncate_events`].
+    pub(crate) fn event_lengths(&self) -> Vec<usize> {
+        macro_rules! lengths {
+            ($self:ident, $($events:ident),*) => {
+                vec![$($self.$events.len()),*]
+            };
+        }
+        for_each_event_vec!(lengths, self)
+    }
+
+    /// Truncates every event vector to the lengths returned by [`Self::event_lengths`].
+    ///
+    /// Note: byte lookups are not truncated, since the executor does not emit them.
+    pub(crate) fn truncate_events(&mut self, lengths: &[usize]) {
+        macro_rules! truncate {
+            ($self:ident, $($events:ident),*) => {
+                let mut lengths = lengths.iter();
+                $($self.$events.truncate(*lengths.next().unwrap());)*
+            };
+        }
+        for_each_event_vec!(truncate, self);
+    }
+
+    /// Truncates every event vector to at most `per_category` events, keeping the first ones.
+    ///
+    /// This keeps a manageable prefix of a large record for analysis. If the nonces were
+    /// registered, they are registered again so that they index the remaining events.
+    ///
+    /// Note: `byte_lookups` are left untouched, so they may still count lookups made by the
+    /// events that were dropped.
+    pub fn truncate_to(&mut self, per_category: usize) {
+        macro_rules! truncate {
+            ($self:ident, $($events:ident),*) => {
+                $($self.$events.truncate(per_category);)*
+            };
+        }
+        for_each_event_vec!(truncate, self);
+
+        if !self.nonce_lookup.is_empty() {
+            self.nonce_lookup.clear();
+            self.register_nonces(&SP1CoreOpts::default());
+        }
+    }
+
     /// Take out events from the [`ExecutionRecord`] that should be deferred to a separate shard.
     ///
     /// Note: we usually defer events that would increase the recursion cost significantly if
     /// included in every shard.
+    ///
+    /// Deferred events keep the `shard` they were generated in, so the shards they end up in after
+    /// [`Self::split`] can still be ordered by their source shard.
     #[must_use]
     pub fn defer(&mut self) -> ExecutionRecord {
         ExecutionRecord {
@@ -156,99 +639,481 @@ impl ExecutionRecord {
         }
     }
 
-    /// Splits the deferred [`ExecutionRecord`] into multiple [`ExecutionRecord`]s, each which
-    /// contain a "reasonable" number of deferred events.
-    pub fn split(&mut self, last: bool, opts: SplitOpts) -> Vec<ExecutionRecord> {
-        let mut shards = Vec::new();
+    /// Estimates the size in bytes of the record once serialized, without serializing it.
+    ///
+    /// The estimate uses the in-memory size of each event, so heap data owned by events (e.g. the
+    /// memory records of precompile events) is not accounted for.
+    #[must_use]
+    pub fn serialized_size_estimate(&self) -> usize {
+        macro_rules! events_size {
+            ($self:ident, $($events:ident),*) => {
+                0 $(+ std::mem::size_of_val($self.$events.as_slice()))*
+            };
+        }
+        let events_size = for_each_event_vec!(events_size, self);
 
-        macro_rules! split_events {
-            ($self:ident, $events:ident, $shards:ident, $threshold:expr, $exact:expr) => {
-                let events = std::mem::take(&mut $self.$events);
-                let chunks = events.chunks_exact($threshold);
-                if !$exact {
-                    $self.$events = chunks.remainder().to_vec();
-                } else {
-                    let remainder = chunks.remainder().to_vec();
-                    if !remainder.is_empty() {
-                        $shards.push(ExecutionRecord {
-                            $events: chunks.remainder().to_vec(),
-                            program: self.program.clone(),
-                            ..Default::default()
+        // Maps are serialized as a length prefix followed by their entries.
+        let byte_lookups_size = self
+            .byte_lookups
+            .values()
+            .map(|lookups| {
+                std::mem::size_of::<u32>()
+                    + std::mem::size_of::<u64>()
+                    + lookups.len()
+                        * (std::mem::size_of::<ByteLookupEvent>() + std::mem::size_of::<u64>())
+            })
+            .sum::<usize>();
+        let nonce_lookup_size =
+            self.nonce_lookup.len() * (std::mem::size_of::<u128>() + std::mem::size_of::<u32>());
+
+        events_size
+            + byte_lookups_size
+            + nonce_lookup_size
+            + std::mem::size_of::<PublicValues<u32, u32>>()
+    }
+
+    /// Checks that the timestamped events of the record are ordered by `(shard, clk)`.
+    ///
+    /// CPU and precompile events each occupy their own `(shard, clk)` slot, so they must be
+    /// strictly increasing. ALU events may share the slot of the instruction that emitted them
+    /// (e.g. the auxiliary lookups of a division), so they only need to be non-decreasing.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ClkOrderError`] describing the first event found out of order.
+    pub fn assert_clk_monotonic(&self) -> Result<(), ClkOrderError> {
+        macro_rules! check_order {
+            ($events:ident, $strict:expr) => {
+                for (index, (prev, event)) in self.$events.iter().tuple_windows().enumerate() {
+                    let (prev, slot) = ((prev.shard, prev.clk), (event.shard, event.clk));
+                    if slot < prev || ($strict && slot == prev) {
+                        return Err(ClkOrderError {
+                            events: stringify!($events),
+                            index: index + 1,
+                            shard: event.shard,
+                            clk: event.clk,
                         });
                     }
                 }
-                let mut event_shards = chunks
-                    .map(|chunk| ExecutionRecord {
-                        $events: chunk.to_vec(),
-                        program: self.program.clone(),
-                        ..Default::default()
-                    })
-                    .collect::<Vec<_>>();
-                $shards.append(&mut event_shards);
             };
         }
 
-        split_events!(self, keccak_permute_events, shards, opts.keccak, last);
-        split_events!(self, secp256k1_add_events, shards, opts.deferred, last);
-        split_events!(self, secp256k1_double_events, shards, opts.deferred, last);
-        split_events!(self, bn254_add_events, shards, opts.deferred, last);
-        split_events!(self, bn254_double_events, shards, opts.deferred, last);
-        split_events!(self, bls12381_add_events, shards, opts.deferred, last);
-        split_events!(self, bls12381_double_events, shards, opts.deferred, last);
-        split_events!(self, sha_extend_events, shards, opts.sha_extend, last);
-        split_events!(self, sha_compress_events, shards, opts.sha_compress, last);
-        split_events!(self, ed_add_events, shards, opts.deferred, last);
-        split_events!(self, ed_decompress_events, shards, opts.deferred, last);
-        split_events!(self, k256_decompress_events, shards, opts.deferred, last);
-        split_events!(self, uint256_mul_events, shards, opts.deferred, last);
-        split_events!(self, bls12381_decompress_events, shards, opts.deferred, last);
-        // _ = last_pct;
+        check_order!(cpu_events, true);
+        check_order!(add_events, false);
+        check_order!(mul_events, false);
+        check_order!(sub_events, false);
+        check_order!(bitwise_events, false);
+        check_order!(shift_left_events, false);
+        check_order!(shift_right_events, false);
+        check_order!(divrem_events, false);
+        check_order!(lt_events, false);
+        check_order!(sha_extend_events, true);
+        check_order!(sha_compress_events, true);
+        check_order!(keccak_permute_events, true);
+        check_order!(ed_add_events, true);
+        check_order!(ed_decompress_events, true);
+        check_order!(secp256k1_add_events, true);
+        check_order!(secp256k1_double_events, true);
+        check_order!(bn254_add_events, true);
+        check_order!(bn254_double_events, true);
+        check_order!(k256_decompress_events, true);
+        check_order!(bls12381_add_events, true);
+        check_order!(bls12381_double_events, true);
+        check_order!(uint256_mul_events, true);
+        check_order!(bls12381_decompress_events, true);
+        Ok(())
+    }
 
-        if last {
-            // shards.push(last_shard);
+    /// Appends `other` to this record like [`MachineRecord::append`], first spot-checking that
+    /// the ALU events of `other` compute the result they record.
+    ///
+    /// Each ALU event is checked with probability `sample_rate`, drawing from `rng`, which trades
+    /// the cost of the check for the chance of catching corrupted events early. The events are
+    /// only checked in debug builds; release builds append without checking.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`InconsistentAluEvent`] for the first sampled event whose result is wrong, in
+    /// which case nothing is appended.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sample_rate` is not in `[0, 1]`.
+    pub fn append_checked(
+        &mut self,
+        other: &mut ExecutionRecord,
+        sample_rate: f64,
+        rng: &mut impl Rng,
+    ) -> Result<(), InconsistentAluEvent> {
+        assert!((0.0..=1.0).contains(&sample_rate), "the sample rate must be in [0, 1]");
+        if cfg!(debug_assertions) {
+            macro_rules! check_events {
+                ($events:ident) => {
+                    for (index, event) in other.$events.iter().enumerate() {
+                        if !rng.gen_bool(sample_rate) {
+                            continue;
+                        }
+                        let expected = event.expected_a();
+                        if event.a != expected {
+                            return Err(InconsistentAluEvent {
+                                events: stringify!($events),
+                                index,
+                                opcode: event.opcode,
+                                a: event.a,
+                                expected,
+                            });
+                        }
+                    }
+                };
+            }
 
-            self.memory_initialize_events.sort_by_key(|event| event.addr);
-            self.memory_finalize_events.sort_by_key(|event| event.addr);
+            check_events!(add_events);
+            check_events!(sub_events);
+            check_events!(mul_events);
+            check_events!(bitwise_events);
+            check_events!(shift_left_events);
+            check_events!(shift_right_events);
+            check_events!(divrem_events);
+            check_events!(lt_events);
+        }
 
-            let mut init_addr_bits = [0; 32];
-            let mut finalize_addr_bits = [0; 32];
-            for mem_chunks in self
-                .memory_initialize_events
-                .chunks(opts.memory)
-                .zip_longest(self.memory_finalize_events.chunks(opts.memory))
-            {
-                let (mem_init_chunk, mem_finalize_chunk) = match mem_chunks {
-                    EitherOrBoth::Both(mem_init_chunk, mem_finalize_chunk) => {
-                        (mem_init_chunk, mem_finalize_chunk)
+        self.append(other);
+        Ok(())
+    }
+
+    /// Checks that the `lookup_id` of every ALU event has a nonce in `nonce_lookup`.
+    ///
+    /// This catches events that were added after [`MachineRecord::register_nonces`] ran.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`UnregisteredNonce`] describing the first event without a nonce.
+    pub fn assert_all_nonces_registered(&self) -> Result<(), UnregisteredNonce> {
+        macro_rules! check_nonces {
+            ($events:ident) => {
+                for (index, event) in self.$events.iter().enumerate() {
+                    if !self.nonce_lookup.contains_key(&event.lookup_id) {
+                        return Err(UnregisteredNonce {
+                            events: stringify!($events),
+                            index,
+                            lookup_id: event.lookup_id,
+                        });
                     }
-                    EitherOrBoth::Left(mem_init_chunk) => (mem_init_chunk, [].as_slice()),
-                    EitherOrBoth::Right(mem_finalize_chunk) => ([].as_slice(), mem_finalize_chunk),
-                };
-                let mut shard = ExecutionRecord::default();
-                shard.program = self.program.clone();
-                shard.memory_initialize_events.extend_from_slice(mem_init_chunk);
-                shard.public_values.previous_init_addr_bits = init_addr_bits;
-                if let Some(last_event) = mem_init_chunk.last() {
-                    let last_init_addr_bits = core::array::from_fn(|i| (last_event.addr >> i) & 1);
-                    init_addr_bits = last_init_addr_bits;
                 }
-                shard.public_values.last_init_addr_bits = init_addr_bits;
-
-                shard.memory_finalize_events.extend_from_slice(mem_finalize_chunk);
-                shard.public_values.previous_finalize_addr_bits = finalize_addr_bits;
-                if let Some(last_event) = mem_finalize_chunk.last() {
-                    let last_finalize_addr_bits =
-                        core::array::from_fn(|i| (last_event.addr >> i) & 1);
-                    finalize_addr_bits = last_finalize_addr_bits;
-                }
-                shard.public_values.last_finalize_addr_bits = finalize_addr_bits;
+            };
+        }
+
+        check_nonces!(add_events);
+        check_nonces!(sub_events);
+        check_nonces!(mul_events);
+        check_nonces!(bitwise_events);
+        check_nonces!(shift_left_events);
+        check_nonces!(shift_right_events);
+        check_nonces!(divrem_events);
+        check_nonces!(lt_events);
+        Ok(())
+    }
+
+    /// Checks that every address is initialized at most once in `memory_initialize_events`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`DuplicateInitAddr`] describing the first repeated address.
+    pub fn assert_unique_init_addresses(&self) -> Result<(), DuplicateInitAddr> {
+        let mut seen = HashSet::with_capacity(self.memory_initialize_events.len());
+        for (index, event) in self.memory_initialize_events.iter().enumerate() {
+            if !seen.insert(event.addr) {
+                return Err(DuplicateInitAddr { index, addr: event.addr });
+            }
+        }
+        Ok(())
+    }
 
-                shards.push(shard);
+    /// Computes the [`SplitOpts`] with which [`Self::split`] produces shards whose traces are close
+    /// to `target_rows` rows high.
+    ///
+    /// The events of each category are spread evenly over the fewest shards that keep every shard
+    /// at or below `target_rows` rows.
+    #[must_use]
+    pub fn auto_split_opts(&self, target_rows: usize) -> SplitOpts {
+        let threshold = |num_events: usize, rows_per_event: usize| {
+            let max_events = (target_rows / rows_per_event).max(1);
+            if num_events == 0 {
+                return max_events;
             }
+            num_events.div_ceil(num_events.div_ceil(max_events))
+        };
+
+        let num_deferred_events = [
+            self.secp256k1_add_events.len(),
+            self.secp256k1_double_events.len(),
+            self.bn254_add_events.len(),
+            self.bn254_double_events.len(),
+            self.bls12381_add_events.len(),
+            self.bls12381_double_events.len(),
+            self.ed_add_events.len(),
+            self.ed_decompress_events.len(),
+            self.k256_decompress_events.len(),
+            self.uint256_mul_events.len(),
+            self.bls12381_decompress_events.len(),
+        ]
+        .into_iter()
+        .max()
+        .unwrap_or_default();
+        let num_memory_events =
+            self.memory_initialize_events.len().max(self.memory_finalize_events.len());
+
+        SplitOpts {
+            deferred: threshold(num_deferred_events, 1),
+            keccak: threshold(self.keccak_permute_events.len(), 24),
+            sha_extend: threshold(self.sha_extend_events.len(), 48),
+            sha_compress: threshold(self.sha_compress_events.len(), 80),
+            memory: threshold(num_memory_events, 1),
+            pack_memory: false,
         }
+    }
+
+    /// Splits the cpu events into shards of `rows_per_shard` events, moving each ALU event into the
+    /// shard whose cpu events span its `(shard, clk)`.
+    ///
+    /// ALU events that fall outside of every shard, as well as all other events, are left in
+    /// `self`. The cpu events are expected to be ordered by `(shard, clk)`.
+    pub fn split_cpu(&mut self, rows_per_shard: usize) -> Vec<ExecutionRecord> {
+        let cpu_events = std::mem::take(&mut self.cpu_events);
+        let chunks = cpu_events.chunks(rows_per_shard);
+        let ranges = chunks
+            .clone()
+            .map(|chunk| {
+                let (first, last) = (&chunk[0], &chunk[chunk.len() - 1]);
+                ((first.shard, first.clk), (last.shard, last.clk))
+            })
+            .collect::<Vec<_>>();
+        let mut shards = chunks
+            .map(|chunk| ExecutionRecord {
+                cpu_events: chunk.to_vec(),
+                program: self.program.clone(),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+
+        macro_rules! split_alu_events {
+            ($($events:ident),*) => {
+                $(
+                    for event in std::mem::take(&mut self.$events) {
+                        let key = (event.shard, event.clk);
+                        let index = ranges.partition_point(|(_, end)| *end < key);
+                        match ranges.get(index) {
+                            Some((start, _)) if *start <= key => shards[index].$events.push(event),
+                            _ => self.$events.push(event),
+                        }
+                    }
+                )*
+            };
+        }
+        split_alu_events!(
+            add_events,
+            sub_events,
+            mul_events,
+            bitwise_events,
+            shift_left_events,
+            shift_right_events,
+            divrem_events,
+            lt_events
+        );
 
         shards
     }
+
+    /// Splits the deferred [`ExecutionRecord`] into multiple [`ExecutionRecord`]s, each which
+    /// contain a "reasonable" number of deferred events.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `memory` threshold of `opts` is zero.
+    pub fn split(&mut self, last: bool, opts: SplitOpts) -> Vec<ExecutionRecord> {
+        self.split_iter(last, opts).collect()
+    }
+
+    /// Splits the deferred [`ExecutionRecord`] like [`Self::split`], but carves out each shard
+    /// only when the iterator is advanced.
+    ///
+    /// The events to split are moved out of `self` up front (unless `last` is set, the events that
+    /// don't fill a whole shard are left in `self`), so only one shard is built at a time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `memory` threshold of `opts` is zero.
+    pub fn split_iter(
+        &mut self,
+        last: bool,
+        opts: SplitOpts,
+    ) -> impl Iterator<Item = ExecutionRecord> {
+        assert!(opts.memory > 0, "the memory split threshold must be positive");
+        let mut events = ExecutionRecord::default();
+        let mut num_events = Vec::new();
+        macro_rules! take_events {
+            ($events:ident, $threshold:ident) => {
+                let num_input = self.$events.len();
+                events.$events = std::mem::take(&mut self.$events);
+                if !last {
+                    self.$events =
+                        events.$events.split_off(num_input - num_input % opts.$threshold);
+                }
+                num_events.push((num_input, self.$events.len()));
+            };
+        }
+        for_each_split_event_vec!(take_events);
+
+        let mut mem_chunks = Vec::new();
+        if last {
+            events.memory_initialize_events = std::mem::take(&mut self.memory_initialize_events);
+            events.memory_finalize_events = std::mem::take(&mut self.memory_finalize_events);
+            events.memory_initialize_events.sort_by_key(|event| event.addr);
+            events.memory_finalize_events.sort_by_key(|event| event.addr);
+            // The executor initializes each address once, so this is only checked in debug builds.
+            debug_assert_eq!(events.assert_unique_init_addresses(), Ok(()));
+
+            let num_init = events.memory_initialize_events.len();
+            let num_finalize = events.memory_finalize_events.len();
+            if opts.pack_memory {
+                // Fill each shard with up to `opts.memory` events, taking initialize events first.
+                let (mut init, mut finalize) = (0, 0);
+                while init < num_init || finalize < num_finalize {
+                    let init_end = num_init.min(init + opts.memory);
+                    let finalize_end = num_finalize.min(finalize + opts.memory - (init_end - init));
+                    mem_chunks.push((init..init_end, finalize..finalize_end));
+                    (init, finalize) = (init_end, finalize_end);
+                }
+            } else {
+                let chunks = |len: usize| {
+                    (0..len)
+                        .step_by(opts.memory)
+                        .map(move |start| start..len.min(start + opts.memory))
+                };
+                mem_chunks = chunks(num_init)
+                    .zip_longest(chunks(num_finalize))
+                    .map(|mem_chunks| match mem_chunks {
+                        EitherOrBoth::Both(init_chunk, finalize_chunk) => {
+                            (init_chunk, finalize_chunk)
+                        }
+                        EitherOrBoth::Left(init_chunk) => (init_chunk, 0..0),
+                        EitherOrBoth::Right(finalize_chunk) => (0..0, finalize_chunk),
+                    })
+                    .collect();
+            }
+        }
+
+        SplitIter {
+            events,
+            program: self.program.clone(),
+            opts,
+            category: 0,
+            offset: 0,
+            remainder_done: false,
+            num_events,
+            num_split: 0,
+            mem_chunks: mem_chunks.into_iter(),
+            init_addr_bits: [0; 32],
+            finalize_addr_bits: [0; 32],
+        }
+    }
+}
+
+/// The iterator returned by [`ExecutionRecord::split_iter`].
+struct SplitIter {
+    /// The events left to split.
+    events: ExecutionRecord,
+    /// The program of the shards.
+    program: Arc<Program>,
+    /// The options to split with.
+    opts: SplitOpts,
+    /// The position in `for_each_split_event_vec` of the event vector being split.
+    category: usize,
+    /// The number of events of the current event vector already split into full shards.
+    offset: usize,
+    /// Whether the events of the current event vector that don't fill a whole shard were split.
+    remainder_done: bool,
+    /// The number of events of each event vector passed to `split_iter` and left in the record.
+    num_events: Vec<(usize, usize)>,
+    /// The number of events of the current event vector put in shards so far.
+    num_split: usize,
+    /// The ranges of initialize and finalize events of the memory shards left to build.
+    mem_chunks: std::vec::IntoIter<(Range<usize>, Range<usize>)>,
+    /// The address bits of the last initialize event of the previous memory shard.
+    init_addr_bits: [u32; 32],
+    /// The address bits of the last finalize event of the previous memory shard.
+    finalize_addr_bits: [u32; 32],
+}
+
+impl Iterator for SplitIter {
+    type Item = ExecutionRecord;
+
+    fn next(&mut self) -> Option<ExecutionRecord> {
+        let mut category = 0;
+        macro_rules! split_events {
+            ($events:ident, $threshold:ident) => {
+                if self.category == category {
+                    let num_events = self.events.$events.len();
+                    let num_full = num_events - num_events % self.opts.$threshold;
+                    let chunk = if !self.remainder_done && num_full < num_events {
+                        Some(num_full..num_events)
+                    } else if self.offset < num_full {
+                        self.offset += self.opts.$threshold;
+                        Some(self.offset - self.opts.$threshold..self.offset)
+                    } else {
+                        None
+                    };
+                    self.remainder_done = true;
+                    if let Some(chunk) = chunk {
+                        self.num_split += chunk.len();
+                        return Some(ExecutionRecord {
+                            $events: self.events.$events[chunk].to_vec(),
+                            program: self.program.clone(),
+                            ..Default::default()
+                        });
+                    }
+
+                    // Make sure that no events were lost while splitting.
+                    let (num_input, num_left) = self.num_events[category];
+                    debug_assert_eq!(
+                        self.num_split + num_left,
+                        num_input,
+                        "lost {} while splitting",
+                        stringify!($events)
+                    );
+
+                    // Free the events and move on to the next event vector.
+                    self.events.$events = Vec::new();
+                    self.num_split = 0;
+                    self.category += 1;
+                    self.offset = 0;
+                    self.remainder_done = false;
+                }
+                category += 1;
+            };
+        }
+        for_each_split_event_vec!(split_events);
+
+        let (init_chunk, finalize_chunk) = self.mem_chunks.next()?;
+        let mut shard = ExecutionRecord::default();
+        shard.program = self.program.clone();
+
+        let mem_init_chunk = &self.events.memory_initialize_events[init_chunk];
+        shard.memory_initialize_events.extend_from_slice(mem_init_chunk);
+        let mem_finalize_chunk = &self.events.memory_finalize_events[finalize_chunk];
+        shard.memory_finalize_events.extend_from_slice(mem_finalize_chunk);
+
+        let prev = PublicValues {
+            last_init_addr_bits: self.init_addr_bits,
+            last_finalize_addr_bits: self.finalize_addr_bits,
+            ..PublicValues::default()
+        };
+        shard.public_values = shard.begin_shard(&prev);
+        self.init_addr_bits = shard.public_values.last_init_addr_bits;
+        self.finalize_addr_bits = shard.public_values.last_finalize_addr_bits;
+
+        Some(shard)
+    }
 }
 
 /// A memory access record.
@@ -264,49 +1129,319 @@ pub struct MemoryAccessRecord {
     pub memory: Option<MemoryRecordEnum>,
 }
 
+/// An event of an [`ExecutionRecord`] that is not ordered by `(shard, clk)`.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("{events}[{index}] at (shard {shard}, clk {clk}) is out of order")]
+pub struct ClkOrderError {
+    /// The name of the event vector.
+    pub events: &'static str,
+    /// The index of the offending event.
+    pub index: usize,
+    /// The shard of the offending event.
+    pub shard: u32,
+    /// The clock of the offending event.
+    pub clk: u32,
+}
+
+/// An ALU event of an [`ExecutionRecord`] whose `lookup_id` has no registered nonce.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("{events}[{index}] has no nonce registered for lookup id {lookup_id}")]
+pub struct UnregisteredNonce {
+    /// The name of the event vector.
+    pub events: &'static str,
+    /// The index of the offending event.
+    pub index: usize,
+    /// The lookup id of the offending event.
+    pub lookup_id: u128,
+}
+
+/// An ALU event whose result doesn't match its opcode and operands.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("{events}[{index}] computes {opcode} to {a:#x} instead of {expected:#x}")]
+pub struct InconsistentAluEvent {
+    /// The name of the event vector.
+    pub events: &'static str,
+    /// The index of the offending event.
+    pub index: usize,
+    /// The opcode of the offending event.
+    pub opcode: Opcode,
+    /// The result recorded in the offending event.
+    pub a: u32,
+    /// The result of the operation on the operands of the offending event.
+    pub expected: u32,
+}
+
+/// An error while numbering the shards of an execution.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitError {
+    /// A shard number is not representable in the field.
+    #[error("shard {shard} does not fit below the field modulus {modulus}")]
+    ShardOverflow {
+        /// The number the last shard would get.
+        shard: u64,
+        /// The modulus of the field.
+        modulus: u32,
+    },
+}
+
+/// A memory address that is initialized more than once in an [`ExecutionRecord`].
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("memory_initialize_events[{index}] initializes address {addr:#x} again")]
+pub struct DuplicateInitAddr {
+    /// The index of the repeated initialize event.
+    pub index: usize,
+    /// The address that is initialized more than once.
+    pub addr: u32,
+}
+
+/// The number of events in each category of an [`ExecutionRecord`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordStats {
+    /// The number of cpu events.
+    pub cpu_events: usize,
+    /// The number of add events.
+    pub add_events: usize,
+    /// The number of mul events.
+    pub mul_events: usize,
+    /// The number of sub events.
+    pub sub_events: usize,
+    /// The number of bitwise events.
+    pub bitwise_events: usize,
+    /// The number of shift left events.
+    pub shift_left_events: usize,
+    /// The number of shift right events.
+    pub shift_right_events: usize,
+    /// The number of divrem events.
+    pub divrem_events: usize,
+    /// The number of lt events.
+    pub lt_events: usize,
+    /// The number of sha extend events.
+    pub sha_extend_events: usize,
+    /// The number of sha compress events.
+    pub sha_compress_events: usize,
+    /// The number of keccak permute events.
+    pub keccak_permute_events: usize,
+    /// The number of ed add events.
+    pub ed_add_events: usize,
+    /// The number of ed decompress events.
+    pub ed_decompress_events: usize,
+    /// The number of secp256k1 add events.
+    pub secp256k1_add_events: usize,
+    /// The number of secp256k1 double events.
+    pub secp256k1_double_events: usize,
+    /// The number of bn254 add events.
+    pub bn254_add_events: usize,
+    /// The number of bn254 double events.
+    pub bn254_double_events: usize,
+    /// The number of k256 decompress events.
+    pub k256_decompress_events: usize,
+    /// The number of bls12381 add events.
+    pub bls12381_add_events: usize,
+    /// The number of bls12381 double events.
+    pub bls12381_double_events: usize,
+    /// The number of uint256 mul events.
+    pub uint256_mul_events: usize,
+    /// The number of bls12381 decompress events.
+    pub bls12381_decompress_events: usize,
+    /// The number of memory initialize events.
+    pub memory_initialize_events: usize,
+    /// The number of memory finalize events.
+    pub memory_finalize_events: usize,
+    /// The number of distinct byte lookups in the shard of the first cpu event.
+    pub byte_lookups: usize,
+}
+
+/// The ranges spanned by an [`ExecutionRecord`], as returned by [`ExecutionRecord::summarize`].
+///
+/// Each range is the `(min, max)` of the values, or `None` if there are no events to take them
+/// from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordSummary {
+    /// The range of the clocks of the cpu events.
+    pub clk: Option<(u32, u32)>,
+    /// The range of the shards of the cpu events.
+    pub shard: Option<(u32, u32)>,
+    /// The range of the addresses of the memory initialize and finalize events.
+    pub addr: Option<(u32, u32)>,
+    /// The number of events of every kind, excluding byte lookups.
+    pub num_events: usize,
+}
+
+/// The public values of an [`ExecutionRecord`] along with the digests a light client checks
+/// against an on-chain commitment, without any of the events.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct PublicSummary {
+    /// The public values of the record.
+    pub public_values: PublicValues<u32, u32>,
+    /// The committed value digest as bytes, each word in little-endian order.
+    pub committed_value_digest: [u8; 32],
+    /// The deferred proofs digest.
+    pub deferred_proofs_digest: [u32; 8],
+}
+
+/// A chip whose trace is generated from the events of an [`ExecutionRecord`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum ChipKind {
+    /// The CPU chip.
+    Cpu,
+    /// The chip for `ADD` and `SUB`.
+    AddSub,
+    /// The chip for `MUL`, `MULH`, `MULHU` and `MULHSU`.
+    Mul,
+    /// The chip for `XOR`, `OR` and `AND`.
+    Bitwise,
+    /// The chip for `SLL`.
+    ShiftLeft,
+    /// The chip for `SRL` and `SRA`.
+    ShiftRight,
+    /// The chip for `DIV`, `DIVU`, `REM` and `REMU`.
+    DivRem,
+    /// The chip for `SLT` and `SLTU`.
+    Lt,
+    /// The sha256 extend chip.
+    ShaExtend,
+    /// The sha256 compress chip.
+    ShaCompress,
+    /// The keccak256 permute chip.
+    KeccakPermute,
+    /// The edwards add chip.
+    EdAddAssign,
+    /// The edwards decompress chip.
+    EdDecompress,
+    /// The secp256k1 add chip.
+    Secp256k1AddAssign,
+    /// The secp256k1 double chip.
+    Secp256k1DoubleAssign,
+    /// The secp256k1 decompress chip.
+    Secp256k1Decompress,
+    /// The bn254 add chip.
+    Bn254AddAssign,
+    /// The bn254 double chip.
+    Bn254DoubleAssign,
+    /// The bls12381 add chip.
+    Bls12381AddAssign,
+    /// The bls12381 double chip.
+    Bls12381DoubleAssign,
+    /// The bls12381 decompress chip.
+    Bls12381Decompress,
+    /// The uint256 mul chip.
+    Uint256MulMod,
+    /// The memory initialize chip.
+    MemoryInit,
+    /// The memory finalize chip.
+    MemoryFinalize,
+}
+
+impl ChipKind {
+    /// Every chip kind, in order.
+    pub const ALL: [ChipKind; 24] = [
+        ChipKind::Cpu,
+        ChipKind::AddSub,
+        ChipKind::Mul,
+        ChipKind::Bitwise,
+        ChipKind::ShiftLeft,
+        ChipKind::ShiftRight,
+        ChipKind::DivRem,
+        ChipKind::Lt,
+        ChipKind::ShaExtend,
+        ChipKind::ShaCompress,
+        ChipKind::KeccakPermute,
+        ChipKind::EdAddAssign,
+        ChipKind::EdDecompress,
+        ChipKind::Secp256k1AddAssign,
+        ChipKind::Secp256k1DoubleAssign,
+        ChipKind::Secp256k1Decompress,
+        ChipKind::Bn254AddAssign,
+        ChipKind::Bn254DoubleAssign,
+        ChipKind::Bls12381AddAssign,
+        ChipKind::Bls12381DoubleAssign,
+        ChipKind::Bls12381Decompress,
+        ChipKind::Uint256MulMod,
+        ChipKind::MemoryInit,
+        ChipKind::MemoryFinalize,
+    ];
+
+    /// Returns the precompile chip proving the events of `syscall`, if it has one.
+    #[must_use]
+    pub const fn from_syscall(syscall: SyscallCode) -> Option<Self> {
+        let chip = match syscall {
+            SyscallCode::SHA_EXTEND => ChipKind::ShaExtend,
+            SyscallCode::SHA_COMPRESS => ChipKind::ShaCompress,
+            SyscallCode::KECCAK_PERMUTE => ChipKind::KeccakPermute,
+            SyscallCode::ED_ADD => ChipKind::EdAddAssign,
+            SyscallCode::ED_DECOMPRESS => ChipKind::EdDecompress,
+            SyscallCode::SECP256K1_ADD => ChipKind::Secp256k1AddAssign,
+            SyscallCode::SECP256K1_DOUBLE => ChipKind::Secp256k1DoubleAssign,
+            SyscallCode::SECP256K1_DECOMPRESS => ChipKind::Secp256k1Decompress,
+            SyscallCode::BN254_ADD => ChipKind::Bn254AddAssign,
+            SyscallCode::BN254_DOUBLE => ChipKind::Bn254DoubleAssign,
+            SyscallCode::BLS12381_ADD => ChipKind::Bls12381AddAssign,
+            SyscallCode::BLS12381_DOUBLE => ChipKind::Bls12381DoubleAssign,
+            SyscallCode::BLS12381_DECOMPRESS => ChipKind::Bls12381Decompress,
+            SyscallCode::UINT256_MUL => ChipKind::Uint256MulMod,
+            _ => return None,
+        };
+        Some(chip)
+    }
+
+    /// Returns whether the chip proves a precompile, whose events are deferred.
+    #[must_use]
+    pub const fn is_precompile(self) -> bool {
+        matches!(
+            self,
+            ChipKind::ShaExtend
+                | ChipKind::ShaCompress
+                | ChipKind::KeccakPermute
+                | ChipKind::EdAddAssign
+                | ChipKind::EdDecompress
+                | ChipKind::Secp256k1AddAssign
+                | ChipKind::Secp256k1DoubleAssign
+                | ChipKind::Secp256k1Decompress
+                | ChipKind::Bn254AddAssign
+                | ChipKind::Bn254DoubleAssign
+                | ChipKind::Bls12381AddAssign
+                | ChipKind::Bls12381DoubleAssign
+                | ChipKind::Bls12381Decompress
+                | ChipKind::Uint256MulMod
+        )
+    }
+}
+
 impl MachineRecord for ExecutionRecord {
     type Config = SP1CoreOpts;
 
     fn stats(&self) -> HashMap<String, usize> {
-        let mut stats = HashMap::new();
-        stats.insert("cpu_events".to_string(), self.cpu_events.len());
-        stats.insert("add_events".to_string(), self.add_events.len());
-        stats.insert("mul_events".to_string(), self.mul_events.len());
-        stats.insert("sub_events".to_string(), self.sub_events.len());
-        stats.insert("bitwise_events".to_string(), self.bitwise_events.len());
-        stats.insert("shift_left_events".to_string(), self.shift_left_events.len());
-        stats.insert("shift_right_events".to_string(), self.shift_right_events.len());
-        stats.insert("divrem_events".to_string(), self.divrem_events.len());
-        stats.insert("lt_events".to_string(), self.lt_events.len());
-        stats.insert("sha_extend_events".to_string(), self.sha_extend_events.len());
-        stats.insert("sha_compress_events".to_string(), self.sha_compress_events.len());
-        stats.insert("keccak_permute_events".to_string(), self.keccak_permute_events.len());
-        stats.insert("ed_add_events".to_string(), self.ed_add_events.len());
-        stats.insert("ed_decompress_events".to_string(), self.ed_decompress_events.len());
-        stats.insert("secp256k1_add_events".to_string(), self.secp256k1_add_events.len());
-        stats.insert("secp256k1_double_events".to_string(), self.secp256k1_double_events.len());
-        stats.insert("bn254_add_events".to_string(), self.bn254_add_events.len());
-        stats.insert("bn254_double_events".to_string(), self.bn254_double_events.len());
-        stats.insert("k256_decompress_events".to_string(), self.k256_decompress_events.len());
-        stats.insert("bls12381_add_events".to_string(), self.bls12381_add_events.len());
-        stats.insert("bls12381_double_events".to_string(), self.bls12381_double_events.len());
-        stats.insert("uint256_mul_events".to_string(), self.uint256_mul_events.len());
-        stats.insert(
-            "bls12381_decompress_events".to_string(),
-            self.bls12381_decompress_events.len(),
-        );
-        stats.insert("memory_initialize_events".to_string(), self.memory_initialize_events.len());
-        stats.insert("memory_finalize_events".to_string(), self.memory_finalize_events.len());
-        if !self.cpu_events.is_empty() {
-            let shard = self.cpu_events[0].shard;
-            stats.insert(
-                "byte_lookups".to_string(),
-                self.byte_lookups.get(&shard).map_or(0, hashbrown::HashMap::len),
-            );
-        }
+        let stats = self.typed_stats();
+        let mut map = HashMap::new();
+        map.insert("cpu_events".to_string(), stats.cpu_events);
+        map.insert("add_events".to_string(), stats.add_events);
+        map.insert("mul_events".to_string(), stats.mul_events);
+        map.insert("sub_events".to_string(), stats.sub_events);
+        map.insert("bitwise_events".to_string(), stats.bitwise_events);
+        map.insert("shift_left_events".to_string(), stats.shift_left_events);
+        map.insert("shift_right_events".to_string(), stats.shift_right_events);
+        map.insert("divrem_events".to_string(), stats.divrem_events);
+        map.insert("lt_events".to_string(), stats.lt_events);
+        map.insert("sha_extend_events".to_string(), stats.sha_extend_events);
+        map.insert("sha_compress_events".to_string(), stats.sha_compress_events);
+        map.insert("keccak_permute_events".to_string(), stats.keccak_permute_events);
+        map.insert("ed_add_events".to_string(), stats.ed_add_events);
+        map.insert("ed_decompress_events".to_string(), stats.ed_decompress_events);
+        map.insert("secp256k1_add_events".to_string(), stats.secp256k1_add_events);
+        map.insert("secp256k1_double_events".to_string(), stats.secp256k1_double_events);
+        map.insert("bn254_add_events".to_string(), stats.bn254_add_events);
+        map.insert("bn254_double_events".to_string(), stats.bn254_double_events);
+        map.insert("k256_decompress_events".to_string(), stats.k256_decompress_events);
+        map.insert("bls12381_add_events".to_string(), stats.bls12381_add_events);
+        map.insert("bls12381_double_events".to_string(), stats.bls12381_double_events);
+        map.insert("uint256_mul_events".to_string(), stats.uint256_mul_events);
+        map.insert("bls12381_decompress_events".to_string(), stats.bls12381_decompress_events);
+        map.insert("memory_initialize_events".to_string(), stats.memory_initialize_events);
+        map.insert("memory_finalize_events".to_string(), stats.memory_finalize_events);
+        map.insert("byte_lookups".to_string(), stats.byte_lookups);
         // Filter out the empty events.
-        stats.retain(|_, v| *v != 0);
-        stats
+        map.retain(|_, v| *v != 0);
+        map
     }
 
     fn append(&mut self, other: &mut ExecutionRecord) {
@@ -397,3 +1532,750 @@ impl ByteRecord for ExecutionRecord {
         add_sharded_byte_lookup_events(&mut self.byte_lookups, new_events);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+    use rand::{rngs::StdRng, SeedableRng};
+    use sp1_stark::{air::PublicValues, MachineRecord, SP1CoreOpts, SplitOpts};
+
+    use super::{
+        assign_shard_numbers, merge_shards, ChipKind, ClkOrderError, DuplicateInitAddr,
+        ExecutionRecord, InconsistentAluEvent, PublicSummary, RecordSummary, SplitError,
+        UnregisteredNonce,
+    };
+    use crate::{
+        events::{
+            create_alu_lookup_id, AluEvent, ByteLookupEvent, ByteRecord, KeccakPermuteEvent,
+            MemoryInitializeFinalizeEvent, MemoryRecord, ShaExtendEvent,
+        },
+        ByteOpcode, Executor, FallOffPolicy, Instruction, Opcode, Program,
+    };
+
+    fn keccak_event(shard: u32) -> KeccakPermuteEvent {
+        KeccakPermuteEvent {
+            lookup_id: 0,
+            shard,
+            channel: 0,
+            clk: 0,
+            pre_state: [0; 25],
+            post_state: [0; 25],
+            state_read_records: vec![],
+            state_write_records: vec![],
+            state_addr: 0,
+        }
+    }
+
+    fn split_opts(threshold: usize) -> SplitOpts {
+        SplitOpts {
+            deferred: threshold,
+            keccak: threshold,
+            sha_extend: threshold,
+            sha_compress: threshold,
+            memory: threshold,
+            pack_memory: false,
+        }
+    }
+
+    #[test]
+    fn test_split_does_not_lose_events() {
+        let mut record = ExecutionRecord::default();
+        record.keccak_permute_events = (0..5).map(keccak_event).collect();
+
+        let mut shards = record.split(false, split_opts(2));
+        assert_eq!(shards.len(), 2);
+        assert_eq!(record.keccak_permute_events.len(), 1);
+
+        record.keccak_permute_events.extend((5..8).map(keccak_event));
+        shards.extend(record.split(true, split_opts(2)));
+        assert!(record.keccak_permute_events.is_empty());
+
+        let num_events =
+            shards.iter().map(|shard| shard.keccak_permute_events.len()).sum::<usize>();
+        assert_eq!(num_events, 8);
+
+        // The shards are checked against the events left in the record for every category, even
+        // when the iterator is consumed one shard at a time.
+        record.keccak_permute_events = (0..7).map(keccak_event).collect();
+        let sha_extend_event = ShaExtendEvent {
+            lookup_id: 0,
+            shard: 1,
+            channel: 0,
+            clk: 0,
+            w_ptr: 0x100,
+            w_i_minus_15_reads: vec![],
+            w_i_minus_2_reads: vec![],
+            w_i_minus_16_reads: vec![],
+            w_i_minus_7_reads: vec![],
+            w_i_writes: vec![],
+        };
+        record.sha_extend_events = vec![sha_extend_event; 5];
+        let mut shards = record.split_iter(false, split_opts(3));
+        let first = shards.next().unwrap();
+        assert_eq!(first.keccak_permute_events.len(), 3);
+        assert_eq!(shards.count(), 2);
+        assert_eq!(record.keccak_permute_events.len(), 1);
+        assert_eq!(record.sha_extend_events.len(), 2);
+    }
+
+    #[test]
+    fn test_deferred_events_keep_source_shard() {
+        let mut deferred = ExecutionRecord::default();
+        for shard in 1..=3 {
+            let mut record = ExecutionRecord::default();
+            record.keccak_permute_events = (0..4).map(|_| keccak_event(shard)).collect();
+            deferred.append(&mut record.defer());
+        }
+
+        let shards = deferred.split(true, split_opts(6));
+        let source_shards = shards
+            .iter()
+            .map(|shard| {
+                shard.keccak_permute_events.iter().map(|event| event.shard).collect::<Vec<_>>()
+            })
+            .filter(|source_shards| !source_shards.is_empty())
+            .collect::<Vec<_>>();
+        assert_eq!(source_shards, vec![vec![1, 1, 1, 1, 2, 2], vec![2, 2, 3, 3, 3, 3]]);
+    }
+
+    #[test]
+    fn test_byte_lookup_shards() {
+        let mut record = ExecutionRecord::default();
+        for shard in [3, 1, 3] {
+            record.add_byte_lookup_event(ByteLookupEvent::new(
+                shard,
+                0,
+                ByteOpcode::U8Range,
+                0,
+                0,
+                0,
+                0,
+            ));
+        }
+        assert_eq!(record.byte_lookup_shards(), vec![1, 3]);
+    }
+
+    #[test]
+    fn test_dedup_byte_lookups() {
+        let event = |shard, b| ByteLookupEvent::new(shard, 0, ByteOpcode::U8Range, 0, 0, 0, b);
+        let mut record = ExecutionRecord::default();
+        for i in 0..100u8 {
+            record.add_byte_lookup_event(event(1, i % 10));
+        }
+        let misfiled = record.byte_lookups.entry(2).or_default();
+        misfiled.insert(event(1, 0), 5);
+        misfiled.insert(event(2, 0), 0);
+        record.byte_lookups.entry(3).or_default().insert(event(3, 0), 0);
+
+        let total_count = |record: &ExecutionRecord| {
+            record.byte_lookups.values().flat_map(|lookups| lookups.values()).sum::<usize>()
+        };
+        let num_entries = |record: &ExecutionRecord| {
+            record.byte_lookups.values().map(hashbrown::HashMap::len).sum::<usize>()
+        };
+        assert_eq!(total_count(&record), 105);
+        assert_eq!(num_entries(&record), 13);
+
+        record.dedup_byte_lookups();
+        assert_eq!(total_count(&record), 105);
+        assert_eq!(num_entries(&record), 10);
+        assert_eq!(record.byte_lookup_shards(), vec![1]);
+        assert_eq!(record.byte_lookups[&1][&event(1, 0)], 15);
+    }
+
+    #[test]
+    fn test_typed_stats_match_stats() {
+        let mut record = ExecutionRecord::default();
+        record.keccak_permute_events = (0..3).map(keccak_event).collect();
+
+        let typed_stats = record.typed_stats();
+        assert_eq!(typed_stats.keccak_permute_events, 3);
+        assert_eq!(typed_stats.cpu_events, 0);
+
+        let stats = record.stats();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats["keccak_permute_events"], typed_stats.keccak_permute_events);
+    }
+
+    #[test]
+    fn test_distinct_memory_addresses() {
+        let mut record = ExecutionRecord::default();
+        record.memory_initialize_events = [0x100, 0x104, 0x100, 0x200]
+            .into_iter()
+            .map(|addr| MemoryInitializeFinalizeEvent::initialize(addr, 0, true))
+            .collect();
+        assert_eq!(record.distinct_memory_addresses(), 3);
+    }
+
+    #[test]
+    fn test_split_cpu() {
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
+            Instruction::new(Opcode::ADD, 30, 0, 37, false, true),
+            Instruction::new(Opcode::MUL, 31, 30, 29, false, false),
+            Instruction::new(Opcode::SUB, 31, 31, 29, false, false),
+            Instruction::new(Opcode::XOR, 31, 31, 29, false, false),
+        ];
+        let program = Program::new(instructions, 0, 0);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.run().unwrap();
+        let mut record = runtime.records.remove(0);
+        record.add_events.push(AluEvent::new(1, 0, 400, Opcode::ADD, 2, 1, 1));
+
+        let shards = record.split_cpu(2);
+        assert_eq!(shards.len(), 3);
+        assert!(record.cpu_events.is_empty());
+        for shard in &shards {
+            let cpu_clks = shard.cpu_events.iter().map(|event| event.clk).collect::<Vec<_>>();
+            let alu_clks = shard
+                .add_events
+                .iter()
+                .chain(&shard.sub_events)
+                .chain(&shard.mul_events)
+                .chain(&shard.bitwise_events)
+                .map(|event| event.clk)
+                .sorted()
+                .collect::<Vec<_>>();
+            assert_eq!(cpu_clks, alu_clks);
+        }
+
+        // The event outside of the cpu events stays behind.
+        assert_eq!(record.add_events.iter().map(|event| event.clk).collect::<Vec<_>>(), [400]);
+    }
+
+    #[test]
+    fn test_alu_events_in_order() {
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
+            Instruction::new(Opcode::SLL, 30, 29, 3, false, true),
+            Instruction::new(Opcode::MUL, 31, 30, 29, false, false),
+            Instruction::new(Opcode::XOR, 31, 31, 29, false, false),
+            Instruction::new(Opcode::SUB, 31, 31, 29, false, false),
+            Instruction::new(Opcode::SLTU, 31, 29, 31, false, false),
+            Instruction::new(Opcode::ADD, 29, 29, 1, false, true),
+        ];
+        let program = Program::new(instructions, 0, 0);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.run().unwrap();
+
+        let events = runtime.records[0].alu_events_in_order();
+        assert_eq!(
+            events.iter().map(|event| (event.clk, event.opcode)).collect::<Vec<_>>(),
+            [
+                (0, Opcode::ADD),
+                (4, Opcode::SLL),
+                (8, Opcode::MUL),
+                (12, Opcode::XOR),
+                (16, Opcode::SUB),
+                (20, Opcode::SLTU),
+                (24, Opcode::ADD),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_alu_events_to_csv() {
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
+            Instruction::new(Opcode::SLL, 30, 29, 3, false, true),
+            Instruction::new(Opcode::SUB, 31, 30, 29, false, false),
+        ];
+        let program = Program::new(instructions, 0, 0);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.run().unwrap();
+
+        let mut csv = Vec::new();
+        runtime.records[0].alu_events_to_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv, "clk,opcode,a,b,c\n0,add,5,0,5\n4,sll,40,5,3\n8,sub,35,40,5\n");
+    }
+
+    #[test]
+    fn test_trivial_alu_count() {
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
+            Instruction::new(Opcode::ADD, 30, 29, 0, false, true),
+            Instruction::new(Opcode::ADD, 31, 30, 0, false, true),
+            Instruction::new(Opcode::ADD, 28, 31, 0, false, true),
+            Instruction::new(Opcode::ADD, 27, 28, 1, false, true),
+            Instruction::new(Opcode::MUL, 26, 27, 1, false, true),
+            Instruction::new(Opcode::MUL, 25, 27, 2, false, true),
+        ];
+        let program = Program::new(instructions, 0, 0);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.run().unwrap();
+
+        assert_eq!(runtime.records[0].trivial_alu_count(), 4);
+    }
+
+    #[test]
+    fn test_assign_shard_numbers() {
+        // A mock field with 8 elements, so that shard 8 can't be represented.
+        let modulus = 8;
+        let mut records = vec![ExecutionRecord::default(); 3];
+        assign_shard_numbers(&mut records, 5, modulus).unwrap();
+        assert_eq!(
+            records.iter().map(|record| record.public_values.shard).collect_vec(),
+            [5, 6, 7]
+        );
+
+        let mut records = vec![ExecutionRecord::default(); 4];
+        assert_eq!(
+            assign_shard_numbers(&mut records, 5, modulus),
+            Err(SplitError::ShardOverflow { shard: 8, modulus })
+        );
+        assert!(records.iter().all(|record| record.public_values.shard == 0));
+
+        assert_eq!(assign_shard_numbers(&mut [], u32::MAX, modulus), Ok(()));
+    }
+
+    #[test]
+    fn test_summarize() {
+        assert_eq!(ExecutionRecord::default().summarize(), RecordSummary::default());
+
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
+            Instruction::new(Opcode::SW, 29, 0, 0x2000, false, true),
+            Instruction::new(Opcode::LW, 30, 0, 0x1000, false, true),
+            Instruction::new(Opcode::ADD, 31, 30, 29, false, false),
+        ];
+        let program = Program::new(instructions, 0, 0);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.run().unwrap();
+
+        let record = &runtime.records[0];
+        let summary = record.summarize();
+        assert_eq!(summary.clk, Some((0, 12)));
+        assert_eq!(summary.shard, Some((1, 1)));
+        // The registers are initialized and finalized along with the memory.
+        assert_eq!(summary.addr, Some((0, 0x2000)));
+        let stats = record.stats();
+        let num_events = stats.values().sum::<usize>() - stats.get("byte_lookups").unwrap_or(&0);
+        assert_eq!(summary.num_events, num_events);
+    }
+
+    #[test]
+    fn test_begin_shard() {
+        let prev = PublicValues::<u32, u32> {
+            shard: 3,
+            next_pc: 0x20_0800,
+            last_init_addr_bits: core::array::from_fn(|i| u32::from(i == 4)),
+            last_finalize_addr_bits: core::array::from_fn(|i| u32::from(i == 2)),
+            ..PublicValues::default()
+        };
+
+        let mut record = ExecutionRecord::default();
+        record.public_values.shard = 4;
+        record.public_values.next_pc = 0x20_0900;
+        let public_values = record.begin_shard(&prev);
+        assert_eq!(public_values.start_pc, 0x20_0800);
+        assert_eq!((public_values.shard, public_values.next_pc), (4, 0x20_0900));
+        assert_eq!(public_values.previous_init_addr_bits, prev.last_init_addr_bits);
+        assert_eq!(public_values.previous_finalize_addr_bits, prev.last_finalize_addr_bits);
+        // Without memory events, the last addresses carry over.
+        assert_eq!(public_values.last_init_addr_bits, prev.last_init_addr_bits);
+        assert_eq!(public_values.last_finalize_addr_bits, prev.last_finalize_addr_bits);
+
+        record.memory_initialize_events =
+            vec![MemoryInitializeFinalizeEvent::initialize(0x24, 0, true)];
+        let public_values = record.begin_shard(&prev);
+        assert_eq!(
+            public_values.last_init_addr_bits.iter().rev().fold(0, |addr, bit| (addr << 1) | bit),
+            0x24
+        );
+        assert_eq!(public_values.last_finalize_addr_bits, prev.last_finalize_addr_bits);
+    }
+
+    #[test]
+    fn test_public_summary_round_trip() {
+        let mut record = ExecutionRecord::default();
+        record.public_values.committed_value_digest = core::array::from_fn(|i| 0x0403_0201 << i);
+        record.public_values.deferred_proofs_digest = core::array::from_fn(|i| 100 + i as u32);
+        record.public_values.start_pc = 0x20_0800;
+        record.public_values.shard = 3;
+
+        let summary = record.public_summary();
+        assert_eq!(summary.committed_value_digest[..8], [1, 2, 3, 4, 2, 4, 6, 8]);
+
+        let bytes = bincode::serialize(&summary).unwrap();
+        let decoded: PublicSummary = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(decoded.committed_value_digest, summary.committed_value_digest);
+        assert_eq!(decoded.deferred_proofs_digest, record.public_values.deferred_proofs_digest);
+        assert_eq!(decoded.public_values.start_pc, 0x20_0800);
+        assert_eq!(decoded.public_values.shard, 3);
+        assert_eq!(bincode::serialize(&decoded).unwrap(), bytes);
+    }
+
+    #[test]
+    fn test_deferred_categories() {
+        let sha_extend_event = ShaExtendEvent {
+            lookup_id: 0,
+            shard: 1,
+            channel: 0,
+            clk: 0,
+            w_ptr: 0x100,
+            w_i_minus_15_reads: vec![],
+            w_i_minus_2_reads: vec![],
+            w_i_minus_16_reads: vec![],
+            w_i_minus_7_reads: vec![],
+            w_i_writes: vec![],
+        };
+        let mut record = ExecutionRecord::default();
+        record.add_events = vec![AluEvent::new(1, 0, 0, Opcode::ADD, 2, 1, 1)];
+        record.keccak_permute_events = (0..3).map(keccak_event).collect();
+        record.sha_extend_events = vec![sha_extend_event; 2];
+        record.memory_finalize_events =
+            vec![MemoryInitializeFinalizeEvent::finalize_from_record(0, &MemoryRecord::default())];
+
+        let deferred = record.defer();
+        assert_eq!(
+            deferred.deferred_categories(),
+            [(ChipKind::ShaExtend, 2), (ChipKind::KeccakPermute, 3)]
+        );
+        assert!(record.deferred_categories().is_empty());
+    }
+
+    #[test]
+    fn test_required_chips() {
+        let mut record = ExecutionRecord::default();
+        assert!(record.required_chips().is_empty());
+
+        record.keccak_permute_events = (0..3).map(keccak_event).collect();
+        assert_eq!(
+            record.required_chips().into_iter().collect::<Vec<_>>(),
+            [ChipKind::KeccakPermute]
+        );
+
+        record.sub_events = vec![AluEvent::new(1, 0, 0, Opcode::SUB, 0, 1, 1)];
+        assert_eq!(
+            record.required_chips().into_iter().collect::<Vec<_>>(),
+            [ChipKind::AddSub, ChipKind::KeccakPermute]
+        );
+    }
+
+    #[test]
+    fn test_truncate_to() {
+        let mut record = ExecutionRecord::default();
+        record.add_events = vec![AluEvent::new(1, 0, 0, Opcode::ADD, 2, 1, 1); 5];
+        record.sub_events = vec![AluEvent::new(1, 0, 0, Opcode::SUB, 0, 1, 1); 3];
+        record.mul_events = vec![AluEvent::new(1, 0, 0, Opcode::MUL, 1, 1, 1); 1];
+        record.keccak_permute_events = (0..4).map(keccak_event).collect();
+        record.memory_initialize_events =
+            (0..8).map(|addr| MemoryInitializeFinalizeEvent::initialize(addr, 0, true)).collect();
+        for (i, event) in record.add_events.iter_mut().chain(&mut record.sub_events).enumerate() {
+            event.lookup_id = i as u128;
+        }
+        record.register_nonces(&SP1CoreOpts::default());
+
+        record.truncate_to(2);
+        assert!(record.event_lengths().iter().all(|&len| len <= 2));
+        assert_eq!(record.add_events.len(), 2);
+        assert_eq!(record.sub_events.len(), 2);
+        assert_eq!(record.mul_events.len(), 1);
+        assert_eq!(record.keccak_permute_events.len(), 2);
+        assert_eq!(record.memory_initialize_events.len(), 2);
+        assert_eq!(record.nonce_lookup[&record.sub_events[1].lookup_id], 3);
+        record.assert_all_nonces_registered().unwrap();
+    }
+
+    #[test]
+    fn test_grouped_alu_events() {
+        let mut record = ExecutionRecord::default();
+        record.add_events = vec![
+            AluEvent::new(1, 0, 0, Opcode::ADD, 2, 1, 1),
+            AluEvent::new(2, 0, 4, Opcode::ADD, 3, 2, 1),
+            AluEvent::new(1, 0, 8, Opcode::ADD, 4, 3, 1),
+        ];
+        record.bitwise_events = vec![
+            AluEvent::new(1, 0, 12, Opcode::XOR, 1, 3, 2),
+            AluEvent::new(1, 0, 16, Opcode::AND, 2, 3, 2),
+            AluEvent::new(1, 0, 20, Opcode::XOR, 0, 3, 3),
+        ];
+        record.lt_events = vec![AluEvent::new(2, 0, 24, Opcode::SLTU, 1, 1, 2)];
+
+        let groups = record.grouped_alu_events();
+        let clks = |shard, opcode| {
+            groups[&(shard, opcode)].iter().map(|event| event.clk).collect::<Vec<_>>()
+        };
+        assert_eq!(groups.len(), 5);
+        assert_eq!(clks(1, Opcode::ADD), [0, 8]);
+        assert_eq!(clks(2, Opcode::ADD), [4]);
+        assert_eq!(clks(1, Opcode::XOR), [12, 20]);
+        assert_eq!(clks(1, Opcode::AND), [16]);
+        assert_eq!(clks(2, Opcode::SLTU), [24]);
+    }
+
+    #[test]
+    fn test_merge_shards() {
+        let mut record = ExecutionRecord::default();
+        record.keccak_permute_events = (0..7).map(keccak_event).collect();
+        record.memory_initialize_events = (0..25)
+            .map(|addr| MemoryInitializeFinalizeEvent::initialize(4 * addr, 0, true))
+            .collect();
+        let stats = record.typed_stats();
+
+        let mut opts = SplitOpts::new(1 << 10);
+        opts.keccak = 2;
+        opts.memory = 10;
+        let shards = record.split(true, opts);
+        assert!(shards.len() > 1);
+        assert_eq!(merge_shards(shards).typed_stats(), stats);
+        assert_eq!(merge_shards(vec![]).typed_stats(), ExecutionRecord::default().typed_stats());
+    }
+
+    #[test]
+    fn test_split_iter_matches_split() {
+        let mut record = ExecutionRecord::default();
+        record.keccak_permute_events = (0..7).map(keccak_event).collect();
+        record.memory_initialize_events = (0..25)
+            .map(|addr| MemoryInitializeFinalizeEvent::initialize(4 * addr, 0, true))
+            .collect();
+        record.memory_finalize_events = (0..12)
+            .map(|addr| {
+                MemoryInitializeFinalizeEvent::finalize_from_record(
+                    4 * addr,
+                    &MemoryRecord::default(),
+                )
+            })
+            .collect();
+
+        let summary = |shards: Vec<ExecutionRecord>| {
+            shards
+                .into_iter()
+                .map(|shard| {
+                    (
+                        shard.keccak_permute_events.iter().map(|e| e.shard).collect::<Vec<_>>(),
+                        shard.memory_initialize_events.len(),
+                        shard.memory_finalize_events.len(),
+                        shard.public_values.last_init_addr_bits,
+                        shard.public_values.previous_finalize_addr_bits,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        for last in [false, true] {
+            for pack_memory in [false, true] {
+                let opts = split_opts(3).with_pack_memory(pack_memory);
+                let mut split_record = record.clone();
+                let mut iter_record = record.clone();
+                let shards = split_record.split(last, opts);
+                let iter_shards = iter_record.split_iter(last, opts).collect::<Vec<_>>();
+                assert_eq!(summary(shards), summary(iter_shards));
+                assert_eq!(
+                    split_record.keccak_permute_events.len(),
+                    iter_record.keccak_permute_events.len()
+                );
+            }
+        }
+
+        let mut iter = record.split_iter(true, split_opts(3));
+        let first = iter.next().unwrap();
+        assert_eq!(first.keccak_permute_events.iter().map(|e| e.shard).collect::<Vec<_>>(), [6]);
+    }
+
+    #[test]
+    fn test_assert_unique_init_addresses() {
+        let mut record = ExecutionRecord::default();
+        record.memory_initialize_events = [0x100, 0x104, 0x200]
+            .into_iter()
+            .map(|addr| MemoryInitializeFinalizeEvent::initialize(addr, 0, true))
+            .collect();
+        assert_eq!(record.assert_unique_init_addresses(), Ok(()));
+
+        record
+            .memory_initialize_events
+            .push(MemoryInitializeFinalizeEvent::initialize(0x104, 7, true));
+        assert_eq!(
+            record.assert_unique_init_addresses(),
+            Err(DuplicateInitAddr { index: 3, addr: 0x104 })
+        );
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "DuplicateInitAddr { index: 2, addr: 260 }")]
+    fn test_split_rejects_duplicate_init_addresses() {
+        let mut record = ExecutionRecord::default();
+        record.memory_initialize_events = [0x104, 0x100, 0x104]
+            .into_iter()
+            .map(|addr| MemoryInitializeFinalizeEvent::initialize(addr, 0, true))
+            .collect();
+        record.split(true, split_opts(100));
+    }
+
+    #[test]
+    #[should_panic(expected = "the memory split threshold must be positive")]
+    fn test_split_rejects_zero_memory_threshold() {
+        let mut record = ExecutionRecord::default();
+        record.memory_initialize_events =
+            vec![MemoryInitializeFinalizeEvent::initialize(0, 0, true)];
+        record.split(true, split_opts(0).with_pack_memory(true));
+    }
+
+    #[test]
+    fn test_split_packs_memory_events() {
+        let mut record = ExecutionRecord::default();
+        record.memory_initialize_events = (0..1000)
+            .map(|addr| MemoryInitializeFinalizeEvent::initialize(addr, 0, true))
+            .collect();
+        record.memory_finalize_events = (0..10)
+            .map(|addr| {
+                MemoryInitializeFinalizeEvent::finalize_from_record(addr, &MemoryRecord::default())
+            })
+            .collect();
+
+        let num_memory_events = |shard: &ExecutionRecord| {
+            shard.memory_initialize_events.len() + shard.memory_finalize_events.len()
+        };
+
+        let shards = record.clone().split(true, split_opts(100));
+        assert_eq!(shards.iter().map(num_memory_events).max(), Some(110));
+
+        let shards = record.split(true, split_opts(100).with_pack_memory(true));
+        assert_eq!(shards.len(), 11);
+        assert!(shards.iter().all(|shard| num_memory_events(shard) <= 100));
+        assert_eq!(shards.iter().map(num_memory_events).sum::<usize>(), 1010);
+        assert_eq!(shards[10].memory_finalize_events.len(), 10);
+    }
+
+    #[test]
+    fn test_auto_split_opts() {
+        let mut record = ExecutionRecord::default();
+        record.keccak_permute_events = (0..1000).map(|_| keccak_event(1)).collect();
+        record.memory_initialize_events = (0..1000)
+            .map(|addr| MemoryInitializeFinalizeEvent::initialize(addr, 0, true))
+            .collect();
+
+        let target_rows = 24 * 300;
+        let opts = record.auto_split_opts(target_rows);
+        let shards = record.split(true, opts);
+
+        let keccak_heights = shards
+            .iter()
+            .map(|shard| shard.keccak_permute_events.len() * 24)
+            .filter(|height| *height > 0)
+            .collect::<Vec<_>>();
+        assert_eq!(keccak_heights.len(), 4);
+        assert!(keccak_heights
+            .iter()
+            .all(|height| (target_rows * 3 / 4..=target_rows).contains(height)));
+
+        let memory_shards =
+            shards.iter().filter(|shard| !shard.memory_initialize_events.is_empty()).count();
+        assert_eq!(memory_shards, 1);
+    }
+
+    #[test]
+    fn test_assert_clk_monotonic() {
+        let mut record = ExecutionRecord::default();
+        record.add_events = vec![
+            AluEvent::new(1, 0, 4, Opcode::ADD, 2, 1, 1),
+            AluEvent::new(1, 0, 4, Opcode::ADD, 3, 2, 1),
+            AluEvent::new(1, 0, 8, Opcode::ADD, 4, 3, 1),
+        ];
+        record.keccak_permute_events = vec![keccak_event(1), keccak_event(2)];
+        assert_eq!(record.assert_clk_monotonic(), Ok(()));
+
+        // Two precompile calls can't happen at the same clk.
+        record.keccak_permute_events.push(keccak_event(2));
+        let err = record.assert_clk_monotonic().unwrap_err();
+        assert_eq!(
+            err,
+            ClkOrderError { events: "keccak_permute_events", index: 2, shard: 2, clk: 0 }
+        );
+    }
+
+    #[test]
+    fn test_serialized_size_estimate() {
+        let mut record = ExecutionRecord::default();
+        for i in 0..1000 {
+            record.add_events.push(AluEvent::new(1, 0, 4 * i, Opcode::ADD, i + 1, i, 1));
+            record.add_byte_lookup_event(ByteLookupEvent::new(
+                1,
+                0,
+                ByteOpcode::U8Range,
+                0,
+                0,
+                0,
+                i as u8,
+            ));
+        }
+
+        let estimate = record.serialized_size_estimate();
+        let actual = bincode::serialized_size(&record).unwrap() as usize;
+        assert!(estimate.abs_diff(actual) * 10 <= actual, "estimate {estimate}, actual {actual}");
+    }
+
+    #[test]
+    fn test_assert_all_nonces_registered() {
+        let alu_event = |clk| AluEvent {
+            lookup_id: create_alu_lookup_id(),
+            ..AluEvent::new(1, 0, clk, Opcode::ADD, 2, 1, 1)
+        };
+        let mut record = ExecutionRecord::default();
+        record.add_events = vec![alu_event(4), alu_event(8)];
+        record.register_nonces(&SP1CoreOpts::default());
+        assert_eq!(record.assert_all_nonces_registered(), Ok(()));
+
+        let late_event = alu_event(12);
+        record.add_events.push(late_event);
+        assert_eq!(
+            record.assert_all_nonces_registered(),
+            Err(UnregisteredNonce {
+                events: "add_events",
+                index: 2,
+                lookup_id: late_event.lookup_id
+            })
+        );
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn test_append_checked() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut record = ExecutionRecord::default();
+        let mut other = ExecutionRecord::default();
+        other.add_events = vec![AluEvent::new(1, 0, 4, Opcode::ADD, 3, 1, 2)];
+        other.divrem_events = vec![
+            AluEvent::new(1, 0, 8, Opcode::DIVU, u32::MAX, 7, 0),
+            AluEvent::new(1, 0, 12, Opcode::REM, 1, 7, 3),
+        ];
+        record.append_checked(&mut other.clone(), 1.0, &mut rng).unwrap();
+        assert_eq!(record.add_events.len(), 1);
+        assert_eq!(record.divrem_events.len(), 2);
+
+        other.divrem_events[1].a = 2;
+        assert_eq!(
+            record.append_checked(&mut other.clone(), 1.0, &mut rng),
+            Err(InconsistentAluEvent {
+                events: "divrem_events",
+                index: 1,
+                opcode: Opcode::REM,
+                a: 2,
+                expected: 1
+            })
+        );
+        assert_eq!(record.divrem_events.len(), 2);
+
+        // Events outside of the sampled subset are not checked.
+        record.append_checked(&mut other, 0.0, &mut rng).unwrap();
+        assert_eq!(record.divrem_events.len(), 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "the sample rate must be in [0, 1]")]
+    fn test_append_checked_invalid_sample_rate() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let _ = ExecutionRecord::default().append_checked(
+            &mut ExecutionRecord::default(),
+            1.5,
+            &mut rng,
+        );
+    }
+}
@@ -114,4 +114,79 @@ impl Register {
             _ => panic!("invalid register {value}"),
         }
     }
+
+    /// Get the ABI name of the register (e.g. `a0` for `%x10`).
+    #[must_use]
+    pub const fn abi_name(&self) -> &'static str {
+        match self {
+            Register::X0 => "zero",
+            Register::X1 => "ra",
+            Register::X2 => "sp",
+            Register::X3 => "gp",
+            Register::X4 => "tp",
+            Register::X5 => "t0",
+            Register::X6 => "t1",
+            Register::X7 => "t2",
+            Register::X8 => "s0",
+            Register::X9 => "s1",
+            Register::X10 => "a0",
+            Register::X11 => "a1",
+            Register::X12 => "a2",
+            Register::X13 => "a3",
+            Register::X14 => "a4",
+            Register::X15 => "a5",
+            Register::X16 => "a6",
+            Register::X17 => "a7",
+            Register::X18 => "s2",
+            Register::X19 => "s3",
+            Register::X20 => "s4",
+            Register::X21 => "s5",
+            Register::X22 => "s6",
+            Register::X23 => "s7",
+            Register::X24 => "s8",
+            Register::X25 => "s9",
+            Register::X26 => "s10",
+            Register::X27 => "s11",
+            Register::X28 => "t3",
+            Register::X29 => "t4",
+            Register::X30 => "t5",
+            Register::X31 => "t6",
+        }
+    }
+}
+
+/// Returns a human-readable report of the registers that differ between `expected` and `actual`.
+///
+/// Each differing register is reported on its own line using its ABI name, e.g.
+/// `a0 (x10): expected 0x2a got 0x00`.
+#[must_use]
+pub fn register_diff(expected: &[u32; 32], actual: &[u32; 32]) -> String {
+    (0..32)
+        .filter(|&i| expected[i] != actual[i])
+        .map(|i| {
+            let register = Register::from_u32(i as u32);
+            format!(
+                "{} (x{i}): expected {:#04x} got {:#04x}",
+                register.abi_name(),
+                expected[i],
+                actual[i]
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::register_diff;
+
+    #[test]
+    fn test_register_diff() {
+        let mut expected = [0; 32];
+        let actual = [0; 32];
+        assert_eq!(register_diff(&expected, &actual), "");
+
+        expected[10] = 0x2a;
+        assert_eq!(register_diff(&expected, &actual), "a0 (x10): expected 0x2a got 0x00");
+    }
 }
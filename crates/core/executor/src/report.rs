@@ -1,5 +1,5 @@
 use std::{
-    collections::{hash_map::Entry, HashMap},
+    collections::{hash_map::Entry, BTreeMap, BTreeSet, HashMap},
     fmt::{Display, Formatter, Result as FmtResult},
     hash::Hash,
     ops::{Add, AddAssign},
@@ -63,6 +63,25 @@ impl ExecutionReport {
     }
 }
 
+/// Returns the change in the count of each opcode from `a` to `b` (i.e. `b` minus `a`), ordered by
+/// opcode.
+///
+/// Opcodes whose count didn't change are omitted, and missing opcodes count as zero.
+#[must_use]
+pub fn histogram_diff(a: &BTreeMap<Opcode, u64>, b: &BTreeMap<Opcode, u64>) -> Vec<(Opcode, i64)> {
+    let opcodes = a.keys().chain(b.keys()).copied().collect::<BTreeSet<_>>();
+    opcodes
+        .into_iter()
+        .filter_map(|opcode| {
+            let count = |counts: &BTreeMap<Opcode, u64>| {
+                counts.get(&opcode).copied().unwrap_or_default() as i64
+            };
+            let delta = count(b) - count(a);
+            (delta != 0).then_some((opcode, delta))
+        })
+        .collect()
+}
+
 /// Combines two `HashMap`s together. If a key is in both maps, the values are added together.
 fn hashmap_add_assign<K, V>(lhs: &mut HashMap<K, V>, rhs: HashMap<K, V>)
 where
@@ -109,3 +128,33 @@ impl Display for ExecutionReport {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use sp1_stark::SP1CoreOpts;
+
+    use super::histogram_diff;
+    use crate::{Executor, FallOffPolicy, Instruction, Opcode, Program};
+
+    fn opcode_counts(num_adds: usize) -> BTreeMap<Opcode, u64> {
+        let mut instructions = vec![Instruction::new(Opcode::ADD, 29, 0, 1, false, true); num_adds];
+        instructions.push(Instruction::new(Opcode::SUB, 29, 29, 1, false, true));
+        let mut runtime = Executor::new(Program::new(instructions, 0, 0), SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.run().unwrap();
+        runtime.report.opcode_counts.into_iter().collect()
+    }
+
+    #[test]
+    fn test_histogram_diff() {
+        let (before, after) = (opcode_counts(10), opcode_counts(12));
+        assert_eq!(histogram_diff(&before, &after), vec![(Opcode::ADD, 2)]);
+        assert_eq!(histogram_diff(&after, &before), vec![(Opcode::ADD, -2)]);
+        assert!(histogram_diff(&before, &before).is_empty());
+
+        let only_sub = BTreeMap::from([(Opcode::SUB, 1)]);
+        assert_eq!(histogram_diff(&only_sub, &before), vec![(Opcode::ADD, 10)]);
+    }
+}
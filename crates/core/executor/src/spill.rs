@@ -0,0 +1,296 @@
+//! A bounded backend for the memory of the executor, which spills cold pages to a backing store.
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use hashbrown::{HashMap, HashSet};
+use nohash_hasher::BuildNoHashHasher;
+
+use crate::events::MemoryRecord;
+
+/// The number of words in a page of memory, the unit in which memory is spilled.
+pub const SPILL_PAGE_WORDS: u32 = 1024;
+
+/// Returns the page containing the word at `addr`.
+#[must_use]
+pub const fn spill_page(addr: u32) -> u32 {
+    addr / (4 * SPILL_PAGE_WORDS)
+}
+
+/// A backing store for the pages of memory evicted by a [`MemorySpill`].
+pub trait PageStore: Send {
+    /// Save the records of the accessed words of `page`, replacing any saved before.
+    fn save(&mut self, page: u32, records: Vec<(u32, MemoryRecord)>) -> io::Result<()>;
+
+    /// Take back the records saved for `page`, or an empty list if none were saved.
+    fn load(&mut self, page: u32) -> io::Result<Vec<(u32, MemoryRecord)>>;
+}
+
+/// A [`PageStore`] that appends the pages to a file.
+///
+/// The space of the pages that were loaded back isn't reclaimed, so the file grows with the number
+/// of evictions.
+pub struct FilePageStore {
+    file: File,
+    len: u64,
+    pages: HashMap<u32, (u64, usize), BuildNoHashHasher<u32>>,
+}
+
+impl FilePageStore {
+    /// Create a store backed by the file at `path`, truncating it if it exists.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::options().read(true).write(true).create(true).truncate(true).open(path)?;
+        Ok(Self { file, len: 0, pages: HashMap::default() })
+    }
+}
+
+impl PageStore for FilePageStore {
+    fn save(&mut self, page: u32, records: Vec<(u32, MemoryRecord)>) -> io::Result<()> {
+        let bytes = bincode::serialize(&records).map_err(io::Error::other)?;
+        self.file.seek(SeekFrom::Start(self.len))?;
+        self.file.write_all(&bytes)?;
+        self.pages.insert(page, (self.len, bytes.len()));
+        self.len += bytes.len() as u64;
+        Ok(())
+    }
+
+    fn load(&mut self, page: u32) -> io::Result<Vec<(u32, MemoryRecord)>> {
+        let Some((offset, len)) = self.pages.remove(&page) else {
+            return Ok(Vec::new());
+        };
+        let mut bytes = vec![0; len];
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.read_exact(&mut bytes)?;
+        bincode::deserialize(&bytes).map_err(io::Error::other)
+    }
+}
+
+/// Bounds the number of pages of memory kept by the executor, evicting the least recently used
+/// ones to a [`PageStore`] and loading them back when they are accessed again.
+///
+/// This trades speed for footprint: every access has to look up its page, and accessing an evicted
+/// page costs a round trip through the store. The page holding the registers is never evicted.
+///
+/// Memory accesses can't fail, so the first error of the store is kept until the executor takes it
+/// with [`MemorySpill::take_failure`] and fails the execution. A page that couldn't be saved stays
+/// resident.
+pub struct MemorySpill {
+    /// The maximum number of resident pages, not counting the page holding the registers.
+    capacity: usize,
+    /// The store the evicted pages are saved to.
+    store: Box<dyn PageStore>,
+    /// The resident pages, along with the tick of their last access.
+    resident: HashMap<u32, u64, BuildNoHashHasher<u32>>,
+    /// The resident pages keyed by the tick of their last access, least recently used first.
+    recency: BTreeMap<u64, u32>,
+    /// The pages saved to the store.
+    spilled: HashSet<u32, BuildNoHashHasher<u32>>,
+    /// The number of page accesses so far.
+    tick: u64,
+    /// The first error of the store that wasn't taken yet.
+    failure: Option<io::Error>,
+}
+
+impl MemorySpill {
+    /// Create a [`MemorySpill`] keeping at most `capacity` pages resident.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `capacity` is zero.
+    #[must_use]
+    pub fn new(capacity: usize, store: impl PageStore + 'static) -> Self {
+        assert!(capacity > 0, "a memory spill must keep at least one page resident");
+        Self {
+            capacity,
+            store: Box::new(store),
+            resident: HashMap::default(),
+            recency: BTreeMap::new(),
+            spilled: HashSet::default(),
+            tick: 0,
+            failure: None,
+        }
+    }
+
+    /// Get the number of pages currently saved to the store.
+    #[must_use]
+    pub fn num_spilled(&self) -> usize {
+        self.spilled.len()
+    }
+
+    /// Take the first error of the store since the last call, if any.
+    pub fn take_failure(&mut self) -> Option<io::Error> {
+        self.failure.take()
+    }
+
+    /// Make the page of `addr` resident in `memory`, loading it back from the store if it was
+    /// evicted and evicting the least recently used pages past the capacity.
+    pub(crate) fn access(
+        &mut self,
+        memory: &mut HashMap<u32, MemoryRecord, BuildNoHashHasher<u32>>,
+        addr: u32,
+    ) {
+        let page = spill_page(addr);
+        if page == 0 {
+            return;
+        }
+        if self.touch(page) {
+            return;
+        }
+        if self.spilled.remove(&page) {
+            match self.store.load(page) {
+                Ok(records) => memory.extend(records),
+                Err(err) => self.fail(err),
+            }
+        }
+        self.evict(memory);
+    }
+
+    /// Load all the evicted pages back into `memory`.
+    pub(crate) fn load_all(
+        &mut self,
+        memory: &mut HashMap<u32, MemoryRecord, BuildNoHashHasher<u32>>,
+    ) -> io::Result<()> {
+        for page in std::mem::take(&mut self.spilled) {
+            memory.extend(self.store.load(page)?);
+            self.touch(page);
+        }
+        Ok(())
+    }
+
+    /// Forget all the pages, as when the memory is cleared.
+    pub(crate) fn clear(&mut self) {
+        self.resident.clear();
+        self.recency.clear();
+        self.spilled.clear();
+    }
+
+    /// Mark `page` as the most recently used, returning whether it was already resident.
+    fn touch(&mut self, page: u32) -> bool {
+        self.tick += 1;
+        let previous = self.resident.insert(page, self.tick);
+        if let Some(tick) = previous {
+            self.recency.remove(&tick);
+        }
+        self.recency.insert(self.tick, page);
+        previous.is_some()
+    }
+
+    fn fail(&mut self, err: io::Error) {
+        self.failure.get_or_insert(err);
+    }
+
+    fn evict(&mut self, memory: &mut HashMap<u32, MemoryRecord, BuildNoHashHasher<u32>>) {
+        let mut kept = Vec::new();
+        while self.resident.len() - kept.len() > self.capacity {
+            let Some((tick, page)) = self.recency.pop_first() else {
+                break;
+            };
+            let start = page * 4 * SPILL_PAGE_WORDS;
+            let addrs = (0..SPILL_PAGE_WORDS)
+                .map(|i| start + 4 * i)
+                .filter(|addr| memory.contains_key(addr))
+                .collect::<Vec<_>>();
+            if !addrs.is_empty() {
+                let records = addrs.iter().map(|addr| (*addr, memory[addr])).collect();
+                if let Err(err) = self.store.save(page, records) {
+                    self.fail(err);
+                    kept.push((tick, page));
+                    continue;
+                }
+                for addr in &addrs {
+                    memory.remove(addr);
+                }
+                self.spilled.insert(page);
+            }
+            self.resident.remove(&page);
+        }
+        self.recency.extend(kept);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sp1_stark::SP1CoreOpts;
+
+    use std::io;
+
+    use super::{FilePageStore, PageStore, SPILL_PAGE_WORDS};
+    use crate::{
+        events::MemoryRecord, ExecutionError, Executor, FallOffPolicy, Instruction, Opcode, Program,
+    };
+
+    /// A [`PageStore`] whose disk is always full.
+    struct FullStore;
+
+    impl PageStore for FullStore {
+        fn save(&mut self, _: u32, _: Vec<(u32, MemoryRecord)>) -> io::Result<()> {
+            Err(io::Error::other("no space left"))
+        }
+
+        fn load(&mut self, _: u32) -> io::Result<Vec<(u32, MemoryRecord)>> {
+            Ok(Vec::new())
+        }
+    }
+
+    #[test]
+    fn test_memory_spill_matches_unbounded() {
+        // Write a word to each of 8 pages, then read them back in a different order.
+        let addr = |page: u32| 0x10000 + page * 4 * SPILL_PAGE_WORDS;
+        let mut instructions = Vec::new();
+        for page in 0..8 {
+            instructions.push(Instruction::new(Opcode::ADD, 29, 0, page + 1, false, true));
+            instructions.push(Instruction::new(Opcode::SW, 29, 0, addr(page), false, true));
+        }
+        for page in [3, 0, 7, 5, 1, 6, 2, 4] {
+            instructions.push(Instruction::new(Opcode::LW, 28, 0, addr(page), false, true));
+            instructions.push(Instruction::new(Opcode::MUL, 30, 30, 10, false, true));
+            instructions.push(Instruction::new(Opcode::ADD, 30, 30, 28, false, false));
+        }
+        let program = Program::new(instructions, 0, 0);
+
+        let mut unbounded = Executor::new(program.clone(), SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        unbounded.run().unwrap();
+
+        let path = std::env::temp_dir().join(format!("sp1-spill-{}.bin", std::process::id()));
+        let store = FilePageStore::create(&path).unwrap();
+        let mut bounded = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt)
+            .with_memory_spill(2, store);
+        bounded.run().unwrap();
+        let spilled_bytes = std::fs::metadata(&path).unwrap().len();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(spilled_bytes > 0);
+        assert_eq!(bounded.registers(), unbounded.registers());
+        assert_eq!(bounded.registers()[30], 41_862_735);
+        let finalize = |runtime: &Executor| {
+            let events = &runtime.records.last().unwrap().memory_finalize_events;
+            let mut events = events
+                .iter()
+                .map(|event| (event.addr, event.value, event.timestamp))
+                .collect::<Vec<_>>();
+            events.sort_unstable();
+            events
+        };
+        assert_eq!(finalize(&bounded), finalize(&unbounded));
+    }
+
+    #[test]
+    fn test_memory_spill_store_failure() {
+        let addr = |page: u32| 0x10000 + page * 4 * SPILL_PAGE_WORDS;
+        let instructions = (0..3)
+            .map(|page| Instruction::new(Opcode::SW, 0, 0, addr(page), false, true))
+            .collect();
+        let mut runtime = Executor::new(Program::new(instructions, 0, 0), SP1CoreOpts::default())
+            .with_memory_spill(1, FullStore);
+        let err = runtime.run().unwrap_err();
+        assert!(matches!(err, ExecutionError::MemorySpill(_)));
+        assert_eq!(err.to_string(), "failed to spill memory: no space left");
+        assert_eq!(runtime.state.global_clk, 1);
+    }
+}
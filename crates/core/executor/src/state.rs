@@ -7,7 +7,9 @@ use hashbrown::HashMap;
 use nohash_hasher::BuildNoHashHasher;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
-use sp1_stark::{baby_bear_poseidon2::BabyBearPoseidon2, ShardProof, StarkVerifyingKey};
+use sp1_stark::{
+    air::PublicValues, baby_bear_poseidon2::BabyBearPoseidon2, ShardProof, StarkVerifyingKey,
+};
 
 use crate::{
     events::MemoryRecord,
@@ -110,6 +112,23 @@ pub struct ForkState {
     pub emit_events: bool,
 }
 
+/// Holds the data needed to revert the runtime to the point before a single step.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct UndoState {
+    pub(crate) global_clk: u64,
+    pub(crate) current_shard: u32,
+    pub(crate) clk: u32,
+    pub(crate) channel: u8,
+    pub(crate) pc: u32,
+    pub(crate) memory_diff: HashMap<u32, Option<MemoryRecord>, BuildNoHashHasher<u32>>,
+    pub(crate) input_stream_ptr: usize,
+    pub(crate) public_values_stream_len: usize,
+    pub(crate) public_values_stream_ptr: usize,
+    pub(crate) num_records: usize,
+    pub(crate) event_lengths: Vec<usize>,
+    pub(crate) public_values: PublicValues<u32, u32>,
+}
+
 impl ExecutionState {
     pub fn save(&self, file: &mut File) -> std::io::Result<()> {
         let mut writer = std::io::BufWriter::new(file);
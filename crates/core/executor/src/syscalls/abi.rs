@@ -0,0 +1,75 @@
+use crate::{Executor, Register};
+
+/// The registers used to pass the number, the arguments and the result of a syscall.
+///
+/// The syscall number is passed in `t0` (`%x5`) and the arguments in `a0` to `a6` (`%x10` to
+/// `%x16`), of which the first two are also passed to [`super::Syscall::execute`]. The result is
+/// written back to `t0`.
+pub struct SyscallAbi;
+
+impl SyscallAbi {
+    /// The register holding the syscall number.
+    pub const NUMBER: Register = Register::X5;
+
+    /// The registers holding the arguments, in order.
+    pub const ARGS: [Register; 7] = [
+        Register::X10,
+        Register::X11,
+        Register::X12,
+        Register::X13,
+        Register::X14,
+        Register::X15,
+        Register::X16,
+    ];
+
+    /// The register the result of the syscall is written to.
+    pub const RETURN: Register = Register::X5;
+
+    /// Get the syscall number.
+    #[must_use]
+    pub fn number(rt: &mut Executor) -> u32 {
+        rt.register(Self::NUMBER)
+    }
+
+    /// Get the `i`-th argument, without creating a memory access record.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `i` is not below 7.
+    #[must_use]
+    pub fn arg(rt: &mut Executor, i: usize) -> u32 {
+        rt.register(Self::ARGS[i])
+    }
+
+    /// Write the result of the syscall, as the `a` operand of the `ECALL`.
+    pub fn set_return(rt: &mut Executor, value: u32) {
+        rt.rw(Self::RETURN, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sp1_stark::SP1CoreOpts;
+
+    use super::SyscallAbi;
+    use crate::{Executor, FallOffPolicy, Instruction, Opcode, Program};
+
+    #[test]
+    fn test_syscall_abi() {
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 5, 0, 0x7f, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, 10, false, true),
+            Instruction::new(Opcode::ADD, 11, 0, 11, false, true),
+            Instruction::new(Opcode::ADD, 12, 0, 12, false, true),
+        ];
+        let mut runtime = Executor::new(Program::new(instructions, 0, 0), SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
+        runtime.run().unwrap();
+
+        assert_eq!(SyscallAbi::number(&mut runtime), 0x7f);
+        assert_eq!(SyscallAbi::arg(&mut runtime, 0), 10);
+        assert_eq!(SyscallAbi::arg(&mut runtime, 1), 11);
+        assert_eq!(SyscallAbi::arg(&mut runtime, 2), 12);
+        assert_eq!(SyscallAbi::arg(&mut runtime, 6), 0);
+    }
+}
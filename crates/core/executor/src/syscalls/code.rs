@@ -91,13 +91,32 @@ pub enum SyscallCode {
 
     /// Executes the `BLS12381_DOUBLE` precompile.
     BLS12381_DOUBLE = 0x00_00_01_1F,
+
+    /// Copies a word-aligned range of memory to a range that doesn't overlap it.
+    MEMCPY = 0x00_01_00_20,
+
+    /// Fills a word-aligned range of memory with a byte.
+    MEMSET = 0x00_00_00_21,
+
+    /// Copies a word-aligned range of memory to a range that may overlap it.
+    MEMMOVE = 0x00_01_00_22,
 }
 
 impl SyscallCode {
     /// Create a [`SyscallCode`] from a u32.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `value` is not a syscall code, see [`Self::try_from_u32`].
     #[must_use]
     pub fn from_u32(value: u32) -> Self {
-        match value {
+        Self::try_from_u32(value).unwrap_or_else(|| panic!("invalid syscall number: {value}"))
+    }
+
+    /// Create a [`SyscallCode`] from a u32, returning `None` if it is not a syscall code.
+    #[must_use]
+    pub fn try_from_u32(value: u32) -> Option<Self> {
+        let code = match value {
             0x00_00_00_00 => SyscallCode::HALT,
             0x00_00_00_02 => SyscallCode::WRITE,
             0x00_00_00_03 => SyscallCode::ENTER_UNCONSTRAINED,
@@ -121,8 +140,12 @@ impl SyscallCode {
             0x00_00_00_F1 => SyscallCode::HINT_READ,
             0x00_01_01_1D => SyscallCode::UINT256_MUL,
             0x00_00_01_1C => SyscallCode::BLS12381_DECOMPRESS,
-            _ => panic!("invalid syscall number: {value}"),
-        }
+            0x00_01_00_20 => SyscallCode::MEMCPY,
+            0x00_00_00_21 => SyscallCode::MEMSET,
+            0x00_01_00_22 => SyscallCode::MEMMOVE,
+            _ => return None,
+        };
+        Some(code)
     }
 
     /// Get the system call identifier.
@@ -0,0 +1,50 @@
+use super::{Syscall, SyscallAbi, SyscallContext};
+
+/// Copies `len` bytes from `src` to `dst`, where `dst` and `src` are given in a0 and a1 and `len`
+/// in a2.
+///
+/// All of `dst`, `src` and `len` must be word-aligned, which the executor checks with
+/// [`crate::ExecutionError::MisalignedMemorySyscall`] before the syscall runs. The whole source
+/// range is read before the destination is written, so overlapping ranges behave like `memmove`.
+/// This implements both `MEMCPY` and `MEMMOVE`; the executor rejects overlapping ranges for
+/// `MEMCPY` with [`crate::ExecutionError::OverlappingMemcpy`] before the syscall runs.
+///
+/// Note: this syscall is not constrained by a chip yet, so it can only be used for execution.
+pub(crate) struct MemCopySyscall;
+
+impl Syscall for MemCopySyscall {
+    fn num_extra_cycles(&self) -> u32 {
+        1
+    }
+
+    fn execute(&self, rt: &mut SyscallContext, arg1: u32, arg2: u32) -> Option<u32> {
+        let (dst, src) = (arg1, arg2);
+        let len = SyscallAbi::arg(rt.rt, 2);
+        let (_, values) = rt.mr_slice(src, (len / 4) as usize);
+
+        // Increment the clk by 1 before writing because we read from memory at the start clk.
+        rt.clk += 1;
+        rt.mw_slice(dst, &values);
+
+        None
+    }
+}
+
+/// Sets `len` bytes starting at `dst` to `byte`, where `dst` and `byte` are given in a0 and a1 and
+/// `len` in a2.
+///
+/// Both `dst` and `len` must be word-aligned, as checked by the executor, and only the low 8 bits of `byte` are used.
+///
+/// Note: this syscall is not constrained by a chip yet, so it can only be used for execution.
+pub(crate) struct MemSetSyscall;
+
+impl Syscall for MemSetSyscall {
+    fn execute(&self, rt: &mut SyscallContext, arg1: u32, arg2: u32) -> Option<u32> {
+        let (dst, byte) = (arg1, arg2 as u8);
+        let len = SyscallAbi::arg(rt.rt, 2);
+        let word = u32::from_le_bytes([byte; 4]);
+        rt.mw_slice(dst, &vec![word; (len / 4) as usize]);
+
+        None
+    }
+}
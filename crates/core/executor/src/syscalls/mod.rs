@@ -1,11 +1,13 @@
 //! Syscall definitions & implementations for the [`crate::Executor`].
 
+mod abi;
 mod code;
 mod commit;
 mod context;
 mod deferred;
 mod halt;
 mod hint;
+mod memory;
 mod precompiles;
 mod unconstrained;
 mod verify;
@@ -18,9 +20,11 @@ use deferred::CommitDeferredSyscall;
 use halt::HaltSyscall;
 use hashbrown::HashMap;
 
+pub use abi::*;
 pub use code::*;
 pub use context::*;
 use hint::{HintLenSyscall, HintReadSyscall};
+use memory::{MemCopySyscall, MemSetSyscall};
 use precompiles::{
     edwards::{add::EdwardsAddAssignSyscall, decompress::EdwardsDecompressSyscall},
     keccak256::permute::Keccak256PermuteSyscall,
@@ -138,3 +142,20 @@ pub fn default_syscall_map() -> HashMap<SyscallCode, Arc<dyn Syscall>> {
 
     syscall_map
 }
+
+/// Creates the map of the memory syscalls.
+///
+/// These syscalls aren't constrained by a chip yet, so they are kept out of the
+/// [`default_syscall_map`] and the programs using them can be executed but not proven.
+#[must_use]
+pub fn memory_syscall_map() -> HashMap<SyscallCode, Arc<dyn Syscall>> {
+    let mut syscall_map = HashMap::<SyscallCode, Arc<dyn Syscall>>::default();
+
+    syscall_map.insert(SyscallCode::MEMCPY, Arc::new(MemCopySyscall));
+
+    syscall_map.insert(SyscallCode::MEMSET, Arc::new(MemSetSyscall));
+
+    syscall_map.insert(SyscallCode::MEMMOVE, Arc::new(MemCopySyscall));
+
+    syscall_map
+}
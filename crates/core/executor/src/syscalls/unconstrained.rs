@@ -36,7 +36,9 @@ impl Syscall for ExitUnconstrainedSyscall {
             ctx.rt.state.clk = ctx.rt.unconstrained_state.clk;
             ctx.rt.state.pc = ctx.rt.unconstrained_state.pc;
             ctx.next_pc = ctx.rt.state.pc.wrapping_add(4);
-            for (addr, value) in ctx.rt.unconstrained_state.memory_diff.drain() {
+            let memory_diff = std::mem::take(&mut ctx.rt.unconstrained_state.memory_diff);
+            for (addr, value) in memory_diff {
+                ctx.rt.page_in(addr);
                 match value {
                     Some(value) => {
                         ctx.rt.state.memory.insert(addr, value);
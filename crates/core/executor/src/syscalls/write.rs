@@ -1,8 +1,8 @@
 use sp1_primitives::consts::num_to_comma_separated;
 
-use crate::{Executor, Register};
+use crate::Executor;
 
-use super::{Syscall, SyscallContext};
+use super::{Syscall, SyscallAbi, SyscallContext};
 
 pub(crate) struct WriteSyscall;
 
@@ -28,11 +28,10 @@ impl Syscall for WriteSyscall {
     /// Else, log a warning.
     #[allow(clippy::pedantic)]
     fn execute(&self, ctx: &mut SyscallContext, arg1: u32, arg2: u32) -> Option<u32> {
-        let a2 = Register::X12;
         let rt = &mut ctx.rt;
         let fd = arg1;
         let write_buf = arg2;
-        let nbytes = rt.register(a2);
+        let nbytes = SyscallAbi::arg(rt, 2);
         // Read nbytes from memory starting at write_buf.
         let bytes = (0..nbytes).map(|i| rt.byte(write_buf + i)).collect::<Vec<u8>>();
         let slice = bytes.as_slice();
@@ -212,10 +212,32 @@ impl CpuChip {
             local.selectors.is_jal,
         );
 
+        // The low bit of the JALR target is cleared to get the new pc. The least significant byte
+        // of the new pc is twice a byte, so it's even and the low bit of the target is unique.
+        builder.when(local.selectors.is_jalr).assert_bool(jump_columns.jalr_target_low_bit);
+        builder
+            .when(local.selectors.is_jalr)
+            .assert_eq(jump_columns.next_pc[0], jump_columns.jalr_next_pc_half_lsb * AB::F::two());
+        builder.send_byte(
+            AB::Expr::from_canonical_u8(ByteOpcode::U8Range as u8),
+            AB::Expr::zero(),
+            jump_columns.jalr_next_pc_half_lsb,
+            AB::Expr::zero(),
+            local.shard,
+            local.channel,
+            local.selectors.is_jalr,
+        );
+        let jalr_target = Word([
+            jump_columns.next_pc[0] + jump_columns.jalr_target_low_bit,
+            jump_columns.next_pc[1].into(),
+            jump_columns.next_pc[2].into(),
+            jump_columns.next_pc[3].into(),
+        ]);
+
         // Verify that the new pc is calculated correctly for JALR instructions.
         builder.send_alu(
             AB::Expr::from_canonical_u32(Opcode::ADD as u32),
-            jump_columns.next_pc,
+            jalr_target,
             local.op_b_val(),
             local.op_c_val(),
             local.shard,
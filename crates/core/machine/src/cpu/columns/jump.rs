@@ -22,4 +22,9 @@ pub struct JumpCols<T> {
 
     pub jal_nonce: T,
     pub jalr_nonce: T,
+
+    /// The low bit of the JALR target `op_b + op_c`, which is cleared in `next_pc`.
+    pub jalr_target_low_bit: T,
+    /// Half of the least significant byte of `next_pc` for JALR, which shows that it is even.
+    pub jalr_next_pc_half_lsb: T,
 }
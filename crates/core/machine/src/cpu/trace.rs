@@ -24,7 +24,7 @@ use super::{
     columns::{CPU_COL_MAP, NUM_CPU_COLS},
     CpuChip,
 };
-use crate::{cpu::columns::CpuCols, memory::MemoryCols};
+use crate::{cpu::columns::CpuCols, memory::MemoryCols, utils::padded_height};
 
 impl<F: PrimeField32> MachineAir<F> for CpuChip {
     type Record = ExecutionRecord;
@@ -181,7 +181,7 @@ impl CpuChip {
         // Populate memory, branch, jump, and auipc specific fields.
         self.populate_memory(cols, event, &mut new_alu_events, blu_events, nonce_lookup);
         self.populate_branch(cols, event, &mut new_alu_events, nonce_lookup);
-        self.populate_jump(cols, event, &mut new_alu_events, nonce_lookup);
+        self.populate_jump(cols, event, &mut new_alu_events, blu_events, nonce_lookup);
         self.populate_auipc(cols, event, &mut new_alu_events, nonce_lookup);
         let is_halt = self.populate_ecall(cols, event, nonce_lookup);
 
@@ -512,6 +512,7 @@ impl CpuChip {
         cols: &mut CpuCols<F>,
         event: &CpuEvent,
         alu_events: &mut HashMap<Opcode, Vec<AluEvent>>,
+        blu_events: &mut impl ByteRecord,
         nonce_lookup: &HashMap<u128, u32>,
     ) {
         if event.instruction.is_jump_instruction() {
@@ -547,10 +548,23 @@ impl CpuChip {
                         .or_insert(vec![add_event]);
                 }
                 Opcode::JALR => {
-                    let next_pc = event.b.wrapping_add(event.c);
+                    let target = event.b.wrapping_add(event.c);
+                    let next_pc = target & !1;
                     jump_columns.op_a_range_checker.populate(event.a);
                     jump_columns.next_pc = Word::from(next_pc);
                     jump_columns.next_pc_range_checker.populate(next_pc);
+                    jump_columns.jalr_target_low_bit = F::from_canonical_u32(target & 1);
+                    let half_lsb = (next_pc & 0xff) >> 1;
+                    jump_columns.jalr_next_pc_half_lsb = F::from_canonical_u32(half_lsb);
+                    blu_events.add_byte_lookup_event(ByteLookupEvent {
+                        shard: event.shard,
+                        channel: event.channel,
+                        opcode: ByteOpcode::U8Range,
+                        a1: 0,
+                        a2: 0,
+                        b: half_lsb as u8,
+                        c: 0,
+                    });
 
                     let add_event = AluEvent {
                         lookup_id: event.jump_jalr_lookup_id,
@@ -558,7 +572,7 @@ impl CpuChip {
                         channel: event.channel,
                         clk: event.clk,
                         opcode: Opcode::ADD,
-                        a: next_pc,
+                        a: target,
                         b: event.b,
                         c: event.c,
                         sub_lookups: create_alu_lookups(),
@@ -698,7 +712,7 @@ impl CpuChip {
 
     fn pad_to_power_of_two<F: PrimeField>(values: &mut Vec<F>) {
         let n_real_rows = values.len() / NUM_CPU_COLS;
-        let padded_nb_rows = if n_real_rows < 16 { 16 } else { n_real_rows.next_power_of_two() };
+        let padded_nb_rows = padded_height(n_real_rows, 16);
         values.resize(padded_nb_rows * NUM_CPU_COLS, F::zero());
 
         // Interpret values as a slice of arrays of length `NUM_CPU_COLS`
@@ -367,7 +367,7 @@ mod tests {
         utils::setup_logger,
     };
     use p3_baby_bear::BabyBear;
-    use sp1_core_executor::{programs::tests::simple_program, Executor};
+    use sp1_core_executor::{programs::tests::simple_program, Executor, FallOffPolicy};
     use sp1_stark::{
         baby_bear_poseidon2::BabyBearPoseidon2, debug_interactions_with_all_chips, SP1CoreOpts,
         StarkMachine,
@@ -376,7 +376,8 @@ mod tests {
     #[test]
     fn test_memory_generate_trace() {
         let program = simple_program();
-        let mut runtime = Executor::new(program, SP1CoreOpts::default());
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
         runtime.run().unwrap();
         let shard = runtime.record.clone();
 
@@ -401,7 +402,8 @@ mod tests {
         setup_logger();
         let program = sha_extend_program();
         let program_clone = program.clone();
-        let mut runtime = Executor::new(program, SP1CoreOpts::default());
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
         runtime.run().unwrap();
         let machine: StarkMachine<BabyBearPoseidon2, RiscvAir<BabyBear>> =
             RiscvAir::machine(BabyBearPoseidon2::new());
@@ -424,7 +426,8 @@ mod tests {
         setup_logger();
         let program = sha_extend_program();
         let program_clone = program.clone();
-        let mut runtime = Executor::new(program, SP1CoreOpts::default());
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
         runtime.run().unwrap();
         let machine = RiscvAir::machine(BabyBearPoseidon2::new());
         let (pkey, _) = machine.setup(&program_clone);
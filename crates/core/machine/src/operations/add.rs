@@ -108,3 +108,43 @@ impl<F: Field> AddOperation<F> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use p3_baby_bear::BabyBear;
+    use p3_field::AbstractField;
+    use sp1_core_executor::events::ByteLookupEvent;
+    use sp1_stark::Word;
+
+    use super::AddOperation;
+    use crate::operations::mock::eval_populated_and_corrupted;
+
+    #[test]
+    fn test_add_eval_is_satisfied() {
+        let inputs = [(0, 0), (1, 2), (0xff, 1), (0xffff_ffff, 1), (0x1234_5678, 0xedcb_a988)];
+        for (a, b) in inputs {
+            let mut cols = AddOperation::<BabyBear>::default();
+            let mut record = Vec::<ByteLookupEvent>::new();
+            cols.populate(&mut record, 1, 0, a, b);
+            assert_eq!(cols.value.to_u32(), a.wrapping_add(b));
+
+            let (populated, corrupted) = eval_populated_and_corrupted(
+                cols,
+                |cols| cols.value[0] += BabyBear::one(),
+                |builder, cols, shard, channel, is_real| {
+                    AddOperation::<BabyBear>::eval(
+                        builder,
+                        Word::from(a),
+                        Word::from(b),
+                        cols,
+                        shard,
+                        channel,
+                        is_real,
+                    );
+                },
+            );
+            assert!(populated.is_satisfied());
+            assert!(!corrupted.is_satisfied());
+        }
+    }
+}
@@ -156,3 +156,44 @@ impl<F: Field> FixedRotateRightOperation<F> {
         builder.assert_eq(cols.value[WORD_SIZE - 1], first_shift + last_carry * carry_multiplier);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use p3_baby_bear::BabyBear;
+    use p3_field::AbstractField;
+    use sp1_core_executor::events::ByteLookupEvent;
+    use sp1_stark::Word;
+
+    use super::FixedRotateRightOperation;
+    use crate::operations::mock::eval_populated_and_corrupted;
+
+    #[test]
+    fn test_fixed_rotate_right_eval_is_satisfied() {
+        for input in [0, 1, 0x8000_0000, 0xffff_ffff, 0x1234_5678] {
+            for rotation in [0, 2, 7, 8, 13, 22, 31] {
+                let mut cols = FixedRotateRightOperation::<BabyBear>::default();
+                let mut record = Vec::<ByteLookupEvent>::new();
+                cols.populate(&mut record, 1, 0, input, rotation);
+
+                let (populated, corrupted) = eval_populated_and_corrupted(
+                    cols,
+                    |cols| cols.value[0] += BabyBear::one(),
+                    |builder, cols, shard, channel, is_real| {
+                        FixedRotateRightOperation::<BabyBear>::eval(
+                            builder,
+                            Word::from(input),
+                            rotation,
+                            cols,
+                            shard,
+                            channel,
+                            is_real,
+                        );
+                    },
+                );
+                assert!(populated.is_satisfied());
+                assert_eq!(populated.byte_lookups().count(), 4);
+                assert!(!corrupted.is_satisfied());
+            }
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use p3_air::{AirBuilder, AirBuilderWithPublicValues};
+use p3_field::{Field, PrimeField32};
+use p3_matrix::dense::RowMajorMatrix;
+use sp1_core_executor::ByteOpcode;
+use sp1_stark::{
+    air::{AirInteraction, MessageBuilder},
+    InteractionKind,
+};
+
+/// An [`AirBuilder`] over concrete field values, used to test the `eval` of an operation on the
+/// columns produced by its `populate`.
+///
+/// Instead of failing on the first violated constraint, the builder records the value of every
+/// asserted constraint along with the interactions sent, so a test can check them afterwards.
+pub(crate) struct MockAirBuilder<F: Field> {
+    /// The value of each constraint passed to `assert_zero`.
+    pub(crate) constraints: Vec<F>,
+    /// The interactions sent during the evaluation.
+    pub(crate) sends: Vec<AirInteraction<F>>,
+    main: RowMajorMatrix<F>,
+}
+
+impl<F: Field> MockAirBuilder<F> {
+    /// Creates a builder with no recorded constraints or interactions.
+    pub(crate) fn new() -> Self {
+        Self {
+            constraints: Vec::new(),
+            sends: Vec::new(),
+            main: RowMajorMatrix::new(Vec::new(), 1),
+        }
+    }
+
+    /// Returns the values `[opcode, a1, a2, b, c, shard, channel]` of the byte lookups sent with a
+    /// non-zero multiplicity.
+    pub(crate) fn byte_lookups(&self) -> impl Iterator<Item = &[F]> {
+        self.sends
+            .iter()
+            .filter(|send| send.kind == InteractionKind::Byte && !send.multiplicity.is_zero())
+            .map(|send| send.values.as_slice())
+    }
+}
+
+impl<F: PrimeField32> MockAirBuilder<F> {
+    /// Returns whether every asserted constraint evaluated to zero and every byte lookup sent is
+    /// valid.
+    pub(crate) fn is_satisfied(&self) -> bool {
+        self.constraints.iter().all(F::is_zero) && self.byte_lookups_are_valid()
+    }
+
+    /// Returns whether the results of the byte lookups match their inputs. Only `XOR` lookups are
+    /// checked, the others are assumed to be valid.
+    pub(crate) fn byte_lookups_are_valid(&self) -> bool {
+        let xor = F::from_canonical_u8(ByteOpcode::XOR as u8);
+        self.byte_lookups().filter(|values| values[0] == xor).all(|values| {
+            let [a, b, c] = [1, 3, 4].map(|i| values[i].as_canonical_u32());
+            a == b ^ c
+        })
+    }
+}
+
+/// Evaluates an operation on its populated columns `cols` and again after `corrupt` changed them,
+/// returning the builders of both evaluations.
+///
+/// `eval` is given the builder, the columns and the shard, channel and multiplicity to evaluate
+/// the operation with, which are one, zero and one.
+pub(crate) fn eval_populated_and_corrupted<F: Field, C: Copy>(
+    cols: C,
+    corrupt: impl FnOnce(&mut C),
+    eval: impl Fn(&mut MockAirBuilder<F>, C, F, F, F),
+) -> (MockAirBuilder<F>, MockAirBuilder<F>) {
+    let mut populated = MockAirBuilder::new();
+    eval(&mut populated, cols, F::one(), F::zero(), F::one());
+
+    let mut cols = cols;
+    corrupt(&mut cols);
+    let mut corrupted = MockAirBuilder::new();
+    eval(&mut corrupted, cols, F::one(), F::zero(), F::one());
+
+    (populated, corrupted)
+}
+
+impl<F: Field> AirBuilder for MockAirBuilder<F> {
+    type F = F;
+    type Expr = F;
+    type Var = F;
+    type M = RowMajorMatrix<F>;
+
+    fn main(&self) -> Self::M {
+        self.main.clone()
+    }
+
+    fn is_first_row(&self) -> Self::Expr {
+        F::one()
+    }
+
+    fn is_last_row(&self) -> Self::Expr {
+        F::one()
+    }
+
+    fn is_transition_window(&self, size: usize) -> Self::Expr {
+        if size == 2 {
+            F::zero()
+        } else {
+            panic!("only supports a window size of 2")
+        }
+    }
+
+    fn assert_zero<I: Into<Self::Expr>>(&mut self, x: I) {
+        self.constraints.push(x.into());
+    }
+}
+
+impl<F: Field> MessageBuilder<AirInteraction<F>> for MockAirBuilder<F> {
+    fn send(&mut self, message: AirInteraction<F>) {
+        self.sends.push(message);
+    }
+
+    fn receive(&mut self, _message: AirInteraction<F>) {
+        panic!("operations only send interactions")
+    }
+}
+
+impl<F: Field> AirBuilderWithPublicValues for MockAirBuilder<F> {
+    type PublicVar = F;
+
+    fn public_values(&self) -> &[Self::PublicVar] {
+        &[]
+    }
+}
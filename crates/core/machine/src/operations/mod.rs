@@ -17,6 +17,8 @@ mod is_equal_word;
 mod is_zero;
 mod is_zero_word;
 mod lt;
+#[cfg(test)]
+mod mock;
 mod not;
 mod or;
 mod xor;
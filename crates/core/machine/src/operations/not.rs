@@ -60,3 +60,41 @@ impl<F: Field> NotOperation<F> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use p3_baby_bear::BabyBear;
+    use p3_field::AbstractField;
+    use sp1_core_executor::events::ByteLookupEvent;
+    use sp1_stark::Word;
+
+    use super::NotOperation;
+    use crate::operations::mock::eval_populated_and_corrupted;
+
+    #[test]
+    fn test_not_eval_is_satisfied() {
+        for x in [0, 1, 0x8000_0000, 0xffff_ffff, 0x1234_5678] {
+            let mut cols = NotOperation::<BabyBear>::default();
+            let mut record = Vec::<ByteLookupEvent>::new();
+            cols.populate(&mut record, 1, 0, x);
+            assert_eq!(cols.value.to_u32(), !x);
+
+            let (populated, corrupted) = eval_populated_and_corrupted(
+                cols,
+                |cols| cols.value[3] = BabyBear::zero(),
+                |builder, cols, shard, channel, is_real| {
+                    NotOperation::<BabyBear>::eval(
+                        builder,
+                        Word::from(x),
+                        cols,
+                        shard,
+                        channel,
+                        is_real,
+                    );
+                },
+            );
+            assert!(populated.is_satisfied());
+            assert_eq!(corrupted.is_satisfied(), x >> 24 == 0xff);
+        }
+    }
+}
@@ -68,3 +68,45 @@ impl<F: Field> XorOperation<F> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use p3_baby_bear::BabyBear;
+    use p3_field::AbstractField;
+    use sp1_core_executor::events::ByteLookupEvent;
+    use sp1_stark::Word;
+
+    use super::XorOperation;
+    use crate::operations::mock::eval_populated_and_corrupted;
+
+    #[test]
+    fn test_xor_eval_is_satisfied() {
+        let inputs = [(0, 0), (0xffff_ffff, 0), (0xf0f0_f0f0, 0xff00_ff00), (0x1234_5678, 0x9abc)];
+        for (a, b) in inputs {
+            let mut cols = XorOperation::<BabyBear>::default();
+            let mut record = Vec::<ByteLookupEvent>::new();
+            cols.populate(&mut record, 1, 0, a, b);
+            assert_eq!(cols.value.to_u32(), a ^ b);
+
+            // The result is only constrained through the byte lookups, which must be valid xors.
+            let (populated, corrupted) = eval_populated_and_corrupted(
+                cols,
+                |cols| cols.value[0] += BabyBear::one(),
+                |builder, cols, shard, channel, is_real| {
+                    XorOperation::<BabyBear>::eval(
+                        builder,
+                        Word::from(a),
+                        Word::from(b),
+                        cols,
+                        shard,
+                        channel,
+                        is_real,
+                    );
+                },
+            );
+            assert!(populated.is_satisfied());
+            assert_eq!(populated.byte_lookups().count(), 4);
+            assert!(!corrupted.is_satisfied());
+        }
+    }
+}
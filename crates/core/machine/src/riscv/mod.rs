@@ -245,6 +245,19 @@ pub mod tests {
         run_test::<CpuProver<_, _>>(program).unwrap();
     }
 
+    #[test]
+    fn test_jalr_odd_target_prove() {
+        utils::setup_logger();
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 12 | 1, false, true),
+            Instruction::new(Opcode::JALR, 1, 29, 0, false, true),
+            Instruction::new(Opcode::ADD, 30, 0, 1, false, true),
+            Instruction::new(Opcode::ADD, 31, 0, 1, false, true),
+        ];
+        let program = Program::new(instructions, 0, 0);
+        run_test::<CpuProver<_, _>>(program).unwrap();
+    }
+
     #[test]
     fn test_add_prove() {
         setup_logger();
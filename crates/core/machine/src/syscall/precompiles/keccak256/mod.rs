@@ -21,7 +21,9 @@ impl KeccakPermuteChip {
 
 #[cfg(test)]
 pub mod permute_tests {
-    use sp1_core_executor::{syscalls::SyscallCode, Executor, Instruction, Opcode, Program};
+    use sp1_core_executor::{
+        syscalls::SyscallCode, Executor, FallOffPolicy, Instruction, Opcode, Program,
+    };
     use sp1_stark::{CpuProver, SP1CoreOpts};
 
     use crate::utils::{self, run_test, tests::KECCAK_PERMUTE_ELF};
@@ -48,7 +50,8 @@ pub mod permute_tests {
     pub fn test_keccak_permute_program_execute() {
         utils::setup_logger();
         let program = keccak_permute_program();
-        let mut runtime = Executor::new(program, SP1CoreOpts::default());
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
         runtime.run().unwrap();
     }
 
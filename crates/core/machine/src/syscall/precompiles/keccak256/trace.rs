@@ -11,6 +11,7 @@ use super::{
     columns::{KeccakMemCols, NUM_KECCAK_MEM_COLS},
     KeccakPermuteChip, STATE_SIZE,
 };
+use crate::utils::padded_height;
 use sp1_core_executor::events::ByteRecord;
 
 impl<F: PrimeField32> MachineAir<F> for KeccakPermuteChip {
@@ -127,10 +128,7 @@ impl<F: PrimeField32> MachineAir<F> for KeccakPermuteChip {
         }
 
         let nb_rows = rows.len();
-        let mut padded_nb_rows = nb_rows.next_power_of_two();
-        if padded_nb_rows == 2 || padded_nb_rows == 1 {
-            padded_nb_rows = 4;
-        }
+        let padded_nb_rows = padded_height(nb_rows, 4);
         if padded_nb_rows > nb_rows {
             let dummy_keccak_rows = generate_trace_rows::<F>(vec![[0; STATE_SIZE]]);
             let mut dummy_rows = Vec::new();
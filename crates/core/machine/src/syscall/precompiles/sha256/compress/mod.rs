@@ -32,11 +32,72 @@ impl ShaCompressChip {
 #[cfg(test)]
 pub mod compress_tests {
 
-    use sp1_core_executor::{syscalls::SyscallCode, Instruction, Opcode, Program};
+    use std::borrow::Borrow;
+
+    use p3_baby_bear::BabyBear;
+    use sp1_core_executor::{
+        events::{MemoryReadRecord, MemoryWriteRecord, ShaCompressEvent},
+        syscalls::SyscallCode,
+        Instruction, Opcode, Program,
+    };
     use sp1_stark::CpuProver;
 
     use crate::utils::{run_test, setup_logger, tests::SHA_COMPRESS_ELF};
 
+    use super::{columns::ShaCompressCols, ShaCompressChip, SHA_COMPRESS_K};
+
+    /// The SHA-256 initial hash values.
+    const SHA256_IV: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+        0x5be0cd19,
+    ];
+
+    /// The SHA-256 digest of "abc".
+    const SHA256_ABC: [u32; 8] = [
+        0xba7816bf, 0x8f01cfea, 0x414140de, 0x5dae2223, 0xb00361a3, 0x96177a9c, 0xb410ff61,
+        0xf20015ad,
+    ];
+
+    /// Computes the message schedule of the padded single block message "abc".
+    fn abc_message_schedule() -> Vec<u32> {
+        let mut w = vec![0u32; 64];
+        w[0] = 0x61626380;
+        w[15] = 0x18;
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+        }
+        w
+    }
+
+    /// Computes the SHA-256 compression function outside of the AIR.
+    fn reference_compress(h: [u32; 8], w: &[u32]) -> [u32; 8] {
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut hh] = h;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let temp1 = hh
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(SHA_COMPRESS_K[i])
+                .wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let temp2 = s0.wrapping_add(maj);
+            hh = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(temp1);
+            d = c;
+            c = b;
+            b = a;
+            a = temp1.wrapping_add(temp2);
+        }
+        let v = [a, b, c, d, e, f, g, hh];
+        core::array::from_fn(|i| h[i].wrapping_add(v[i]))
+    }
+
     pub fn sha_compress_program() -> Program {
         let w_ptr = 100;
         let h_ptr = 1000;
@@ -62,6 +123,43 @@ pub mod compress_tests {
         Program::new(instructions, 0, 0)
     }
 
+    #[test]
+    fn generate_compress_rows() {
+        let w = abc_message_schedule();
+        let digest = reference_compress(SHA256_IV, &w);
+        assert_eq!(digest, SHA256_ABC);
+
+        let event = ShaCompressEvent {
+            lookup_id: 0,
+            shard: 1,
+            channel: 0,
+            clk: 1,
+            w_ptr: 100,
+            h_ptr: 1000,
+            w: w.clone(),
+            h: SHA256_IV,
+            h_read_records: core::array::from_fn(|i| {
+                MemoryReadRecord::new(SHA256_IV[i], 1, 1, 0, 0)
+            }),
+            w_i_read_records: w.iter().map(|&w_i| MemoryReadRecord::new(w_i, 1, 1, 0, 0)).collect(),
+            h_write_records: core::array::from_fn(|i| {
+                MemoryWriteRecord::new(digest[i], 1, 1, SHA256_IV[i], 0, 0)
+            }),
+        };
+
+        let rows = ShaCompressChip::new().generate_compress_rows::<BabyBear>(&event);
+        assert_eq!(rows.len(), 80);
+
+        let output = rows[72..]
+            .iter()
+            .map(|row| {
+                let cols: &ShaCompressCols<BabyBear> = row.as_slice().borrow();
+                cols.finalize_add.value.to_u32()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(output, SHA256_ABC);
+    }
+
     #[test]
     fn prove_babybear() {
         setup_logger();
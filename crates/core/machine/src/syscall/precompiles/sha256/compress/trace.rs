@@ -31,14 +31,11 @@ impl<F: PrimeField32> MachineAir<F> for ShaCompressChip {
         input: &ExecutionRecord,
         _: &mut ExecutionRecord,
     ) -> RowMajorMatrix<F> {
-        let rows = Vec::new();
-
-        let mut wrapped_rows = Some(rows);
-        for i in 0..input.sha_compress_events.len() {
-            let event = input.sha_compress_events[i].clone();
-            self.event_to_rows(&event, &mut wrapped_rows, &mut Vec::new());
-        }
-        let mut rows = wrapped_rows.unwrap();
+        let mut rows = input
+            .sha_compress_events
+            .iter()
+            .flat_map(|event| self.generate_compress_rows::<F>(event))
+            .collect::<Vec<_>>();
 
         let num_real_rows = rows.len();
 
@@ -108,6 +105,20 @@ impl<F: PrimeField32> MachineAir<F> for ShaCompressChip {
 }
 
 impl ShaCompressChip {
+    /// Generates the 80 trace rows for a single [`ShaCompressEvent`].
+    ///
+    /// The first 8 rows load the working variables `a..h` from `H`, the next 64 rows perform the
+    /// compression rounds (including the additions of the round constants `K` and the message
+    /// schedule `W`), and the last 8 rows add the working variables back into `H`.
+    pub fn generate_compress_rows<F: PrimeField32>(
+        &self,
+        event: &ShaCompressEvent,
+    ) -> Vec<[F; NUM_SHA_COMPRESS_COLS]> {
+        let mut rows = Some(Vec::with_capacity(80));
+        self.event_to_rows(event, &mut rows, &mut Vec::new());
+        rows.unwrap()
+    }
+
     fn event_to_rows<F: PrimeField32>(
         &self,
         event: &ShaCompressEvent,
@@ -5,6 +5,9 @@ mod trace;
 
 pub use columns::*;
 
+use p3_field::PrimeField32;
+use thiserror::Error;
+
 /// Implements the SHA extension operation which loops over i = [16, 63] and modifies w[i] in each
 /// iteration. The only input to the syscall is the 4byte-aligned pointer to the w array.
 ///
@@ -19,22 +22,80 @@ impl ShaExtendChip {
     }
 }
 
+/// The SHA-256 message schedule function `σ0(x) := (x rightrotate 7) xor (x rightrotate 18) xor
+/// (x rightshift 3)`, as specified in FIPS 180-4.
+pub const fn reference_s0(x: u32) -> u32 {
+    x.rotate_right(7) ^ x.rotate_right(18) ^ (x >> 3)
+}
+
+/// The SHA-256 message schedule function `σ1(x) := (x rightrotate 17) xor (x rightrotate 19) xor
+/// (x rightshift 10)`, as specified in FIPS 180-4.
+pub const fn reference_s1(x: u32) -> u32 {
+    x.rotate_right(17) ^ x.rotate_right(19) ^ (x >> 10)
+}
+
 pub fn sha_extend(w: &mut [u32]) {
     for i in 16..64 {
-        let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
-        let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+        let s0 = reference_s0(w[i - 15]);
+        let s1 = reference_s1(w[i - 2]);
         w[i] = w[i - 16] + s0 + w[i - 7] + s1;
     }
 }
 
+/// The offsets `k` of the words `w[i - k]` read by each row of the extend chip.
+const READ_OFFSETS: [u32; 4] = [15, 2, 16, 7];
+
+/// A row of the extend chip whose reads do not point at `w_ptr + 4 * (i - k)` of its event.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("row {row} reads w[i - {k}] at {actual:#x}, expected {expected:#x}")]
+pub struct PtrError {
+    /// The index of the row in the trace.
+    pub row: usize,
+    /// The offset of the read, as in `w[i - k]`.
+    pub k: u32,
+    /// The address implied by the `w_ptr` and `i` columns of the row.
+    pub actual: u32,
+    /// The address implied by the first row of the event and the position of the row within it.
+    pub expected: u32,
+}
+
+/// Check the pointer arithmetic of the memory reads of the extend chip.
+///
+/// The read columns don't store their address: the AIR reads `w[i - k]` at `w_ptr + 4 * (i - k)`.
+/// Each real row is therefore checked against the `w_ptr` of the first row of its 48-row event and
+/// `i = 16 + j` for the `j`-th row of the event, catching a corrupted `w_ptr` or an off-by-one `i`.
+/// Padding rows are ignored.
+pub fn validate_extend_ptrs<F: PrimeField32>(rows: &[ShaExtendCols<F>]) -> Result<(), PtrError> {
+    for (event, chunk) in rows.chunks(48).enumerate() {
+        let w_ptr = chunk[0].w_ptr.as_canonical_u32();
+        for (j, cols) in chunk.iter().enumerate().filter(|(_, cols)| cols.is_real.is_one()) {
+            let i = cols.i.as_canonical_u32();
+            for k in READ_OFFSETS {
+                let actual =
+                    cols.w_ptr.as_canonical_u32().wrapping_add(i.wrapping_sub(k).wrapping_mul(4));
+                let expected = w_ptr + (16 + j as u32 - k) * 4;
+                if actual != expected {
+                    return Err(PtrError { row: 48 * event + j, k, actual, expected });
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 pub mod extend_tests {
 
+    use std::borrow::Borrow;
+
     use p3_baby_bear::BabyBear;
+    use p3_field::AbstractField;
 
-    use p3_matrix::dense::RowMajorMatrix;
+    use p3_matrix::{dense::RowMajorMatrix, Matrix};
     use sp1_core_executor::{
-        events::AluEvent, syscalls::SyscallCode, ExecutionRecord, Instruction, Opcode, Program,
+        events::{AluEvent, MemoryReadRecord, MemoryWriteRecord, ShaExtendEvent},
+        syscalls::SyscallCode,
+        ExecutionRecord, Instruction, Opcode, Program,
     };
     use sp1_stark::{air::MachineAir, CpuProver};
 
@@ -43,7 +104,9 @@ pub mod extend_tests {
         tests::{SHA2_ELF, SHA_EXTEND_ELF},
     };
 
-    use super::ShaExtendChip;
+    use super::{
+        reference_s0, reference_s1, validate_extend_ptrs, PtrError, ShaExtendChip, ShaExtendCols,
+    };
 
     pub fn sha_extend_program() -> Program {
         let w_ptr = 100;
@@ -73,6 +136,89 @@ pub mod extend_tests {
         println!("{:?}", trace.values)
     }
 
+    #[test]
+    fn test_s0_s1_populate_matches_reference() {
+        let mut inputs = vec![0, 1, u32::MAX, 0x8000_0000, 0x7fff_ffff, 0xaaaa_aaaa];
+        let mut x = 0x6a09_e667u32;
+        while inputs.len() < 4 * 48 {
+            x = x.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            inputs.push(x);
+        }
+
+        let read = |value| MemoryReadRecord {
+            value,
+            shard: 1,
+            timestamp: 1,
+            prev_shard: 1,
+            prev_timestamp: 0,
+        };
+        let write =
+            MemoryWriteRecord { shard: 1, timestamp: 1, prev_shard: 1, ..Default::default() };
+        let mut shard = ExecutionRecord::default();
+        for chunk in inputs.chunks(48) {
+            shard.sha_extend_events.push(ShaExtendEvent {
+                lookup_id: 0,
+                shard: 1,
+                channel: 0,
+                clk: 1,
+                w_ptr: 0,
+                w_i_minus_15_reads: chunk.iter().map(|&x| read(x)).collect(),
+                w_i_minus_2_reads: chunk.iter().map(|&x| read(!x)).collect(),
+                w_i_minus_16_reads: vec![read(0); 48],
+                w_i_minus_7_reads: vec![read(0); 48],
+                w_i_writes: vec![write; 48],
+            });
+        }
+
+        let trace: RowMajorMatrix<BabyBear> =
+            ShaExtendChip::new().generate_trace(&shard, &mut ExecutionRecord::default());
+        for (i, &x) in inputs.iter().enumerate() {
+            let row = trace.row_slice(i);
+            let cols: &ShaExtendCols<BabyBear> = (*row).borrow();
+            assert_eq!(cols.s0.value.to_u32(), reference_s0(x), "s0({x:#x})");
+            assert_eq!(cols.s1.value.to_u32(), reference_s1(!x), "s1({:#x})", !x);
+        }
+    }
+
+    #[test]
+    fn test_validate_extend_ptrs() {
+        let read = |value| MemoryReadRecord { value, shard: 1, timestamp: 1, ..Default::default() };
+        let write =
+            MemoryWriteRecord { shard: 1, timestamp: 1, prev_shard: 1, ..Default::default() };
+        let mut shard = ExecutionRecord::default();
+        for w_ptr in [0x100, 0x400] {
+            shard.sha_extend_events.push(ShaExtendEvent {
+                lookup_id: 0,
+                shard: 1,
+                channel: 0,
+                clk: 1,
+                w_ptr,
+                w_i_minus_15_reads: vec![read(1); 48],
+                w_i_minus_2_reads: vec![read(2); 48],
+                w_i_minus_16_reads: vec![read(3); 48],
+                w_i_minus_7_reads: vec![read(4); 48],
+                w_i_writes: vec![write; 48],
+            });
+        }
+
+        let trace: RowMajorMatrix<BabyBear> =
+            ShaExtendChip::new().generate_trace(&shard, &mut ExecutionRecord::default());
+        let mut rows: Vec<ShaExtendCols<BabyBear>> = (0..trace.height())
+            .map(|i| {
+                let row = trace.row_slice(i);
+                let cols: &ShaExtendCols<BabyBear> = (*row).borrow();
+                *cols
+            })
+            .collect();
+        assert_eq!(validate_extend_ptrs(&rows), Ok(()));
+
+        rows[50].w_ptr += BabyBear::from_canonical_u32(4);
+        assert_eq!(
+            validate_extend_ptrs(&rows),
+            Err(PtrError { row: 50, k: 15, actual: 0x400 + 4 * 3 + 4, expected: 0x400 + 4 * 3 })
+        );
+    }
+
     #[test]
     fn test_sha_prove() {
         utils::setup_logger();
@@ -17,9 +17,13 @@ pub use tracer::*;
 #[cfg(any(test, feature = "programs"))]
 pub use programs::*;
 
+use std::collections::BTreeMap;
+
 use crate::memory::MemoryCols;
 use generic_array::ArrayLength;
+use p3_keccak_air::NUM_ROUNDS;
 use p3_maybe_rayon::prelude::{ParallelBridge, ParallelIterator};
+use sp1_core_executor::{ChipKind, ExecutionRecord};
 
 pub const fn indices_arr<const N: usize>() -> [usize; N] {
     let mut indices_arr = [0; N];
@@ -31,13 +35,49 @@ pub const fn indices_arr<const N: usize>() -> [usize; N] {
     indices_arr
 }
 
+/// Returns the height of a trace with `count` rows once padded, i.e. `count` rounded up to a power
+/// of two and to at least `min` rows.
+///
+/// An empty trace is padded to `min` rows, so it stays empty if `min` is zero.
+pub const fn padded_height(count: usize, min: usize) -> usize {
+    if count == 0 {
+        return min;
+    }
+    let height = count.next_power_of_two();
+    if height < min {
+        min
+    } else {
+        height
+    }
+}
+
+/// Returns the padded trace height of each chip whose rows are generated from the events of
+/// `record`.
+///
+/// Chips without events are omitted, as are the preprocessed `Program`, `MemoryProgram` and `Byte`
+/// chips, whose heights do not depend on the events.
+pub fn chip_heights(record: &ExecutionRecord) -> BTreeMap<ChipKind, usize> {
+    record
+        .chip_events()
+        .into_iter()
+        .filter(|(_, num_events)| *num_events > 0)
+        .map(|(chip, num_events)| {
+            // (rows per event, minimum padded height)
+            let (rows_per_event, min_height) = match chip {
+                ChipKind::ShaExtend => (48, 4),
+                ChipKind::ShaCompress => (80, 16),
+                ChipKind::KeccakPermute => (NUM_ROUNDS, 4),
+                _ => (1, 16),
+            };
+            (chip, padded_height(num_events * rows_per_event, min_height))
+        })
+        .collect()
+}
+
 pub fn pad_to_power_of_two<const N: usize, T: Clone + Default>(values: &mut Vec<T>) {
     debug_assert!(values.len() % N == 0);
-    let mut n_real_rows = values.len() / N;
-    if n_real_rows < 16 {
-        n_real_rows = 16;
-    }
-    values.resize(n_real_rows.next_power_of_two() * N, T::default());
+    let n_real_rows = values.len() / N;
+    values.resize(padded_height(n_real_rows, 16) * N, T::default());
 }
 
 pub fn limbs_from_prev_access<T: Copy, N: ArrayLength, M: MemoryCols<T>>(
@@ -58,10 +98,7 @@ pub fn limbs_from_access<T: Copy, N: ArrayLength, M: MemoryCols<T>>(cols: &[M])
 
 pub fn pad_rows<T: Clone>(rows: &mut Vec<T>, row_fn: impl Fn() -> T) {
     let nb_rows = rows.len();
-    let mut padded_nb_rows = nb_rows.next_power_of_two();
-    if padded_nb_rows < 16 {
-        padded_nb_rows = 16;
-    }
+    let padded_nb_rows = padded_height(nb_rows, 16);
     if padded_nb_rows == nb_rows {
         return;
     }
@@ -97,13 +134,7 @@ pub fn next_power_of_two(n: usize, fixed_power: Option<usize>) -> usize {
             }
             padded_nb_rows
         }
-        None => {
-            let mut padded_nb_rows = n.next_power_of_two();
-            if padded_nb_rows < 16 {
-                padded_nb_rows = 16;
-            }
-            padded_nb_rows
-        }
+        None => padded_height(n, 16),
     }
 }
 
@@ -196,3 +227,55 @@ where
         },
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use sp1_core_executor::{
+        events::{AluEvent, KeccakPermuteEvent, MemoryInitializeFinalizeEvent},
+        ChipKind, ExecutionRecord, Opcode,
+    };
+
+    use super::{chip_heights, padded_height};
+
+    #[test]
+    fn test_padded_height() {
+        assert_eq!(padded_height(0, 0), 0);
+        assert_eq!(padded_height(0, 16), 16);
+        assert_eq!(padded_height(1, 0), 1);
+        assert_eq!(padded_height(1, 1), 1);
+        assert_eq!(padded_height(1, 16), 16);
+        assert_eq!(padded_height(16, 16), 16);
+        assert_eq!(padded_height(17, 16), 32);
+        assert_eq!(padded_height(17, 4), 32);
+        assert_eq!(padded_height(17, 64), 64);
+    }
+
+    #[test]
+    fn test_chip_heights() {
+        let keccak_event = KeccakPermuteEvent {
+            lookup_id: 0,
+            shard: 1,
+            channel: 0,
+            clk: 0,
+            pre_state: [0; 25],
+            post_state: [0; 25],
+            state_read_records: vec![],
+            state_write_records: vec![],
+            state_addr: 0,
+        };
+        let mut record = ExecutionRecord::default();
+        record.add_events = vec![AluEvent::new(1, 0, 0, Opcode::ADD, 2, 1, 1); 20];
+        record.sub_events = vec![AluEvent::new(1, 0, 0, Opcode::SUB, 0, 1, 1); 20];
+        record.mul_events = vec![AluEvent::new(1, 0, 0, Opcode::MUL, 1, 1, 1); 3];
+        record.keccak_permute_events = vec![keccak_event; 3];
+        record.memory_initialize_events =
+            (0..64).map(|addr| MemoryInitializeFinalizeEvent::initialize(addr, 0, true)).collect();
+
+        let heights = chip_heights(&record);
+        assert_eq!(heights.len(), 4);
+        assert_eq!(heights[&ChipKind::AddSub], 64);
+        assert_eq!(heights[&ChipKind::Mul], 16);
+        assert_eq!(heights[&ChipKind::KeccakPermute], 128);
+        assert_eq!(heights[&ChipKind::MemoryInit], 64);
+    }
+}
@@ -25,8 +25,8 @@ use crate::{
     utils::{chunk_vec, concurrency::TurnBasedSync},
 };
 use sp1_core_executor::{
-    subproof::NoOpSubproofVerifier, ExecutionError, ExecutionRecord, ExecutionReport, Executor,
-    Program, SP1Context,
+    assign_shard_numbers, subproof::NoOpSubproofVerifier, ExecutionError, ExecutionRecord,
+    ExecutionReport, Executor, FallOffPolicy, Program, SP1Context, SplitError,
 };
 use sp1_stark::{
     air::{MachineAir, PublicValues},
@@ -44,6 +44,8 @@ pub enum SP1CoreProverError {
     IoError(io::Error),
     #[error("serialization error: {0}")]
     SerializationError(bincode::Error),
+    #[error("failed to number shards: {0}")]
+    SplitError(SplitError),
 }
 
 pub fn prove_simple<SC: StarkGenericConfig, P: MachineProver<SC, RiscvAir<SC::Val>>>(
@@ -64,9 +66,8 @@ where
     let (pk, _) = prover.setup(runtime.program.as_ref());
 
     // Set the shard numbers.
-    runtime.records.iter_mut().enumerate().for_each(|(i, shard)| {
-        shard.public_values.shard = (i + 1) as u32;
-    });
+    assign_shard_numbers(&mut runtime.records, 1, <SC::Val as PrimeField32>::ORDER_U32)
+        .map_err(SP1CoreProverError::SplitError)?;
 
     // Prove the program.
     let mut challenger = prover.config().challenger();
@@ -115,6 +116,29 @@ pub fn prove_with_context<SC: StarkGenericConfig, P: MachineProver<SC, RiscvAir<
     opts: SP1CoreOpts,
     context: SP1Context,
 ) -> Result<(MachineProof<SC>, Vec<u8>, u64), SP1CoreProverError>
+where
+    SC::Val: PrimeField32,
+    SC::Challenger: 'static + Clone + Send,
+    OpeningProof<SC>: Send,
+    Com<SC>: Send + Sync,
+    PcsProverData<SC>: Send + Sync,
+{
+    // Shard numbers end up as field elements, so they must stay below the modulus.
+    let modulus = <SC::Val as PrimeField32>::ORDER_U32;
+    prove_with_modulus(prover, pk, program, stdin, opts, context, modulus)
+}
+
+/// Proves `program` like [`prove_with_context`], but numbers the shards as if the field had the
+/// given `modulus`, which lets the tests run out of shard numbers.
+fn prove_with_modulus<SC: StarkGenericConfig, P: MachineProver<SC, RiscvAir<SC::Val>>>(
+    prover: &P,
+    pk: &StarkProvingKey<SC>,
+    program: Program,
+    stdin: &SP1Stdin,
+    opts: SP1CoreOpts,
+    context: SP1Context,
+    modulus: u32,
+) -> Result<(MachineProof<SC>, Vec<u8>, u64), SP1CoreProverError>
 where
     SC::Val: PrimeField32,
     SC::Challenger: 'static + Clone + Send,
@@ -160,8 +184,11 @@ where
                             .save(&mut checkpoint_file)
                             .map_err(SP1CoreProverError::IoError)?;
 
-                        // Send the checkpoint.
-                        checkpoints_tx.send((index, checkpoint_file, done)).unwrap();
+                        // Send the checkpoint. The send only fails once every worker has stopped,
+                        // which they only do early on an error that they report themselves.
+                        if checkpoints_tx.send((index, checkpoint_file, done)).is_err() {
+                            break Ok(runtime.state.public_values_stream);
+                        }
 
                         // If we've reached the final checkpoint, break out of the loop.
                         if done {
@@ -223,6 +250,12 @@ where
                             // Update the public values & prover state for the shards which contain
                             // "cpu events".
                             let mut state = state.lock().unwrap();
+                            if let Err(err) =
+                                check_shard_numbers(state.shard, records.len(), modulus)
+                            {
+                                skip_turns(&record_gen_sync, &trace_gen_sync, index);
+                                return Err(SP1CoreProverError::SplitError(err));
+                            }
                             for record in records.iter_mut() {
                                 state.shard += 1;
                                 state.execution_shard = record.public_values.execution_shard;
@@ -249,6 +282,12 @@ where
                             if !done {
                                 state.execution_shard += 1;
                             }
+                            if let Err(err) =
+                                check_shard_numbers(state.shard, deferred.len(), modulus)
+                            {
+                                skip_turns(&record_gen_sync, &trace_gen_sync, index);
+                                return Err(SP1CoreProverError::SplitError(err));
+                            }
                             for record in deferred.iter_mut() {
                                 state.shard += 1;
                                 state.previous_init_addr_bits =
@@ -295,7 +334,7 @@ where
 
                             trace_gen_sync.advance_turn();
                         } else {
-                            break;
+                            break Ok(());
                         }
                     }
                 })
@@ -303,6 +342,7 @@ where
             p1_record_and_trace_gen_handles.push(handle);
         }
         drop(p1_records_and_traces_tx);
+        drop(checkpoints_rx);
 
         // Create the challenger and observe the verifying key.
         let mut challenger = prover.config().challenger();
@@ -353,12 +393,14 @@ where
             challenger
         });
 
+        // Wait until the records and traces have been fully generated.
+        for handle in p1_record_and_trace_gen_handles {
+            handle.join().unwrap()?;
+        }
+
         // Wait until the checkpoint generator handle has fully finished.
         let public_values_stream = checkpoint_generator_handle.join().unwrap().unwrap();
 
-        // Wait until the records and traces have been fully generated.
-        p1_record_and_trace_gen_handles.into_iter().for_each(|handle| handle.join().unwrap());
-
         // Wait until the phase 1 prover has completely finished.
         let challenger = phase_1_prover_handle.join().unwrap();
 
@@ -411,6 +453,12 @@ where
                             // Update the public values & prover state for the shards which contain
                             // "cpu events".
                             let mut state = state.lock().unwrap();
+                            if let Err(err) =
+                                check_shard_numbers(state.shard, records.len(), modulus)
+                            {
+                                skip_turns(&record_gen_sync, &trace_gen_sync, index);
+                                return Err(SP1CoreProverError::SplitError(err));
+                            }
                             for record in records.iter_mut() {
                                 state.shard += 1;
                                 state.execution_shard = record.public_values.execution_shard;
@@ -437,6 +485,12 @@ where
                             if !done {
                                 state.execution_shard += 1;
                             }
+                            if let Err(err) =
+                                check_shard_numbers(state.shard, deferred.len(), modulus)
+                            {
+                                skip_turns(&record_gen_sync, &trace_gen_sync, index);
+                                return Err(SP1CoreProverError::SplitError(err));
+                            }
                             for record in deferred.iter_mut() {
                                 state.shard += 1;
                                 state.previous_init_addr_bits =
@@ -478,7 +532,7 @@ where
 
                             trace_gen_sync.advance_turn();
                         } else {
-                            break;
+                            break Ok(());
                         }
                     }
                 })
@@ -512,7 +566,9 @@ where
         });
 
         // Wait until the records and traces have been fully generated for phase 2.
-        p2_record_and_trace_gen_handles.into_iter().for_each(|handle| handle.join().unwrap());
+        for handle in p2_record_and_trace_gen_handles {
+            handle.join().unwrap()?;
+        }
 
         // Wait until the phase 2 prover has finished.
         let shard_proofs = p2_prover_handle.join().unwrap();
@@ -553,13 +609,31 @@ where
     })
 }
 
+/// Checks that `count` more shards can be numbered after `shard` while staying below `modulus`.
+fn check_shard_numbers(shard: u32, count: usize, modulus: u32) -> Result<(), SplitError> {
+    let last = u64::from(shard) + count as u64;
+    if count > 0 && last >= u64::from(modulus) {
+        return Err(SplitError::ShardOverflow { shard: last, modulus });
+    }
+    Ok(())
+}
+
+/// Gives up the turns of the worker tracing checkpoint `index`, so that the other workers don't
+/// wait on it after it failed.
+fn skip_turns(record_gen_sync: &TurnBasedSync, trace_gen_sync: &TurnBasedSync, index: usize) {
+    record_gen_sync.advance_turn();
+    trace_gen_sync.wait_for_turn(index);
+    trace_gen_sync.advance_turn();
+}
+
 /// Runs a program and returns the public values stream.
 pub fn run_test_io<P: MachineProver<BabyBearPoseidon2, RiscvAir<BabyBear>>>(
     program: Program,
     inputs: SP1Stdin,
 ) -> Result<SP1PublicValues, MachineVerificationError<BabyBearPoseidon2>> {
     let runtime = tracing::debug_span!("runtime.run(...)").in_scope(|| {
-        let mut runtime = Executor::new(program, SP1CoreOpts::default());
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
         runtime.write_vecs(&inputs.buffer);
         runtime.run().unwrap();
         runtime
@@ -573,7 +647,8 @@ pub fn run_test<P: MachineProver<BabyBearPoseidon2, RiscvAir<BabyBear>>>(
     program: Program,
 ) -> Result<MachineProof<BabyBearPoseidon2>, MachineVerificationError<BabyBearPoseidon2>> {
     let runtime = tracing::debug_span!("runtime.run(...)").in_scope(|| {
-        let mut runtime = Executor::new(program, SP1CoreOpts::default());
+        let mut runtime = Executor::new(program, SP1CoreOpts::default())
+            .with_fall_off_policy(FallOffPolicy::Halt);
         runtime.run().unwrap();
         runtime
     });
@@ -595,7 +670,7 @@ pub fn run_test_core<P: MachineProver<BabyBearPoseidon2, RiscvAir<BabyBear>>>(
         Program::clone(&runtime.program),
         &inputs,
         SP1CoreOpts::default(),
-        SP1Context::default(),
+        SP1Context::builder().fall_off_policy(runtime.fall_off_policy).build(),
     )
     .unwrap();
 
@@ -653,6 +728,8 @@ fn trace_checkpoint(
     // We already passed the deferred proof verifier when creating checkpoints, so the proofs were
     // already verified. So here we use a noop verifier to not print any warnings.
     runtime.subproof_verifier = Arc::new(NoOpSubproofVerifier);
+    // Likewise, the execution already went through the fall off policy when creating checkpoints.
+    runtime.fall_off_policy = FallOffPolicy::Halt;
     let (events, _) = runtime.execute_record().unwrap();
     (events, runtime.report)
 }
@@ -728,3 +805,38 @@ where
 use p3_air::Air;
 use p3_matrix::dense::RowMajorMatrix;
 use p3_uni_stark::Proof;
+
+#[cfg(test)]
+mod tests {
+    use sp1_core_executor::programs::tests::fibonacci_program;
+
+    use super::*;
+
+    #[test]
+    fn test_prove_with_shard_overflow() {
+        let program = fibonacci_program();
+        let mut opts = SP1CoreOpts::default();
+        opts.shard_size = 1024;
+        opts.shard_batch_size = 2;
+        opts.trace_gen_workers = 1;
+        opts.checkpoints_channel_capacity = 1;
+        let prover = CpuProver::new(RiscvAir::machine(BabyBearPoseidon2::new()));
+        let (pk, _) = prover.setup(&program);
+
+        // The checkpoint generator is still executing when the worker runs out of shard numbers,
+        // so this only returns if the generator stops once nobody receives its checkpoints.
+        let result = prove_with_modulus(
+            &prover,
+            &pk,
+            program,
+            &SP1Stdin::new(),
+            opts,
+            SP1Context::default(),
+            4,
+        );
+        assert!(matches!(
+            result,
+            Err(SP1CoreProverError::SplitError(SplitError::ShardOverflow { modulus: 4, .. }))
+        ));
+    }
+}
@@ -5,6 +5,8 @@ use itertools::Itertools;
 use p3_field::{AbstractField, PrimeField32};
 use serde::{Deserialize, Serialize};
 
+use sp1_primitives::consts::WORD_SIZE;
+
 use crate::{Word, PROOF_MAX_NUM_PVS};
 
 /// The number of non padded elements in the SP1 proofs public values vec.
@@ -69,6 +71,27 @@ impl PublicValues<u32, u32> {
         ret
     }
 
+    /// Convert the public values into field elements grouped by kind, in the same layout as
+    /// [`Self::to_vec`].
+    #[must_use]
+    pub fn structured_vec<F: AbstractField>(&self) -> StructuredPv<F> {
+        let pv = PublicValues::<Word<F>, F>::from(*self);
+        let addr_bits = [
+            &pv.previous_init_addr_bits,
+            &pv.last_init_addr_bits,
+            &pv.previous_finalize_addr_bits,
+            &pv.last_finalize_addr_bits,
+        ];
+        StructuredPv {
+            committed_value_digest: core::array::from_fn(|i| {
+                pv.committed_value_digest[i / WORD_SIZE].0[i % WORD_SIZE].clone()
+            }),
+            addr_bits: core::array::from_fn(|i| addr_bits[i / 32][i % 32].clone()),
+            deferred_proofs_digest: pv.deferred_proofs_digest,
+            scalars: [pv.start_pc, pv.next_pc, pv.exit_code, pv.shard, pv.execution_shard],
+        }
+    }
+
     /// Resets the public values to zero.
     #[must_use]
     pub fn reset(&self) -> Self {
@@ -85,6 +108,141 @@ impl PublicValues<u32, u32> {
     }
 }
 
+/// The first broken link found by [`verify_pv_chain`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChainError {
+    /// The chain has no shards.
+    Empty,
+    /// The first shard starts at the halted program counter.
+    ZeroStartPc,
+    /// A shard number isn't one more than the previous shard's, starting at one.
+    ShardNumber {
+        /// The index of the shard in the chain.
+        index: usize,
+        /// The expected shard number.
+        expected: u32,
+        /// The shard number of the shard.
+        actual: u32,
+    },
+    /// A shard doesn't start at the program counter where the previous shard stopped.
+    PcDiscontinuity {
+        /// The index of the shard in the chain.
+        index: usize,
+        /// The `next_pc` of the previous shard.
+        expected: u32,
+        /// The `start_pc` of the shard.
+        actual: u32,
+    },
+    /// The `previous_init_addr_bits` of a shard don't match the `last_init_addr_bits` of the
+    /// previous shard, or aren't zero for the first shard.
+    InitAddrBits {
+        /// The index of the shard in the chain.
+        index: usize,
+    },
+    /// The `previous_finalize_addr_bits` of a shard don't match the `last_finalize_addr_bits` of
+    /// the previous shard, or aren't zero for the first shard.
+    FinalizeAddrBits {
+        /// The index of the shard in the chain.
+        index: usize,
+    },
+}
+
+impl core::fmt::Display for ChainError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ChainError::Empty => write!(f, "the chain has no shards"),
+            ChainError::ZeroStartPc => write!(f, "the first shard starts at pc 0"),
+            ChainError::ShardNumber { index, expected, actual } => {
+                write!(f, "shard {index} has number {actual} instead of {expected}")
+            }
+            ChainError::PcDiscontinuity { index, expected, actual } => {
+                write!(f, "shard {index} starts at pc {actual:#x} instead of {expected:#x}")
+            }
+            ChainError::InitAddrBits { index } => {
+                write!(f, "shard {index} doesn't continue the init address bits")
+            }
+            ChainError::FinalizeAddrBits { index } => {
+                write!(f, "shard {index} doesn't continue the finalize address bits")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ChainError {}
+
+/// Checks that the public values of a sequence of shards link up: the first shard starts at a
+/// non-zero pc, shard numbers count up from one, each shard starts at the `next_pc` of the
+/// previous one, and the memory init and finalize address bits carry over from shard to shard.
+///
+/// # Errors
+///
+/// Returns a [`ChainError`] describing the first broken link.
+pub fn verify_pv_chain(pvs: &[PublicValues<u32, u32>]) -> Result<(), ChainError> {
+    let first = pvs.first().ok_or(ChainError::Empty)?;
+    if first.start_pc == 0 {
+        return Err(ChainError::ZeroStartPc);
+    }
+
+    let mut prev_next_pc = first.start_pc;
+    let mut prev_init_addr_bits = [0; 32];
+    let mut prev_finalize_addr_bits = [0; 32];
+    for (index, pv) in pvs.iter().enumerate() {
+        let expected = index as u32 + 1;
+        if pv.shard != expected {
+            return Err(ChainError::ShardNumber { index, expected, actual: pv.shard });
+        }
+        if pv.start_pc != prev_next_pc {
+            return Err(ChainError::PcDiscontinuity {
+                index,
+                expected: prev_next_pc,
+                actual: pv.start_pc,
+            });
+        }
+        if pv.previous_init_addr_bits != prev_init_addr_bits {
+            return Err(ChainError::InitAddrBits { index });
+        }
+        if pv.previous_finalize_addr_bits != prev_finalize_addr_bits {
+            return Err(ChainError::FinalizeAddrBits { index });
+        }
+        prev_next_pc = pv.next_pc;
+        prev_init_addr_bits = pv.last_init_addr_bits;
+        prev_finalize_addr_bits = pv.last_finalize_addr_bits;
+    }
+    Ok(())
+}
+
+/// The field elements of a [`PublicValues`], grouped by kind.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StructuredPv<F> {
+    /// The bytes of the committed value digest.
+    pub committed_value_digest: [F; PV_DIGEST_NUM_WORDS * WORD_SIZE],
+    /// The deferred proofs digest.
+    pub deferred_proofs_digest: [F; POSEIDON_NUM_WORDS],
+    /// The start pc, next pc, exit code, shard and execution shard.
+    pub scalars: [F; 5],
+    /// The previous and last init address bits, followed by the previous and last finalize
+    /// address bits.
+    pub addr_bits: [F; 4 * 32],
+}
+
+impl<F: AbstractField> StructuredPv<F> {
+    /// Concatenate the groups into a vector of field elements padded to the maximum number of
+    /// public values, like [`PublicValues::to_vec`].
+    #[must_use]
+    pub fn flatten(&self) -> Vec<F> {
+        let mut ret = self
+            .committed_value_digest
+            .iter()
+            .chain(&self.deferred_proofs_digest)
+            .chain(&self.scalars)
+            .chain(&self.addr_bits)
+            .cloned()
+            .collect_vec();
+        ret.resize(PROOF_MAX_NUM_PVS, F::zero());
+        ret
+    }
+}
+
 impl<F: PrimeField32> PublicValues<Word<F>, F> {
     /// Returns the commit digest as a vector of little-endian bytes.
     pub fn commit_digest_bytes(&self) -> Vec<u8> {
@@ -169,11 +327,87 @@ impl<F: AbstractField> From<PublicValues<u32, u32>> for PublicValues<Word<F>, F>
 
 #[cfg(test)]
 mod tests {
-    use crate::air::public_values;
+    use p3_baby_bear::BabyBear;
+
+    use crate::air::public_values::{self, verify_pv_chain, ChainError, PublicValues};
 
     /// Check that the [`PI_DIGEST_NUM_WORDS`] number match the zkVM crate's.
     #[test]
     fn test_public_values_digest_num_words_consistency_zkvm() {
         assert_eq!(public_values::PV_DIGEST_NUM_WORDS, sp1_zkvm::PV_DIGEST_NUM_WORDS);
     }
+
+    #[test]
+    fn test_structured_vec_matches_to_vec() {
+        let pv = PublicValues::<u32, u32> {
+            committed_value_digest: core::array::from_fn(|i| 0x0102_0304 * (i as u32 + 1)),
+            deferred_proofs_digest: core::array::from_fn(|i| 100 + i as u32),
+            start_pc: 0x20_0800,
+            next_pc: 0x20_0900,
+            exit_code: 1,
+            shard: 2,
+            execution_shard: 3,
+            previous_init_addr_bits: core::array::from_fn(|i| (i % 2) as u32),
+            last_init_addr_bits: core::array::from_fn(|i| (i % 3 == 0) as u32),
+            previous_finalize_addr_bits: [1; 32],
+            last_finalize_addr_bits: [0; 32],
+        };
+        assert_eq!(pv.structured_vec::<BabyBear>().flatten(), pv.to_vec::<BabyBear>());
+    }
+
+    fn pv_chain() -> Vec<PublicValues<u32, u32>> {
+        let addr_bits = |addr: u32| core::array::from_fn(|i| (addr >> i) & 1);
+        let shard = |shard, start_pc, next_pc, init: [u32; 2], finalize: [u32; 2]| PublicValues {
+            start_pc,
+            next_pc,
+            shard,
+            previous_init_addr_bits: addr_bits(init[0]),
+            last_init_addr_bits: addr_bits(init[1]),
+            previous_finalize_addr_bits: addr_bits(finalize[0]),
+            last_finalize_addr_bits: addr_bits(finalize[1]),
+            ..Default::default()
+        };
+        vec![
+            shard(1, 0x20_0800, 0x20_0900, [0, 0], [0, 0]),
+            shard(2, 0x20_0900, 0, [0, 0], [0, 0]),
+            shard(3, 0, 0, [0, 0x1000], [0, 0x800]),
+            shard(4, 0, 0, [0x1000, 0x2000], [0x800, 0x800]),
+        ]
+    }
+
+    #[test]
+    fn test_verify_pv_chain() {
+        assert_eq!(verify_pv_chain(&pv_chain()), Ok(()));
+        assert_eq!(verify_pv_chain(&pv_chain()[..1]), Ok(()));
+        assert_eq!(verify_pv_chain(&[]), Err(ChainError::Empty));
+    }
+
+    #[test]
+    fn test_verify_pv_chain_broken_links() {
+        let mut pvs = pv_chain();
+        pvs[0].start_pc = 0;
+        assert_eq!(verify_pv_chain(&pvs), Err(ChainError::ZeroStartPc));
+
+        let mut pvs = pv_chain();
+        pvs[2].shard = 4;
+        assert_eq!(
+            verify_pv_chain(&pvs),
+            Err(ChainError::ShardNumber { index: 2, expected: 3, actual: 4 })
+        );
+
+        let mut pvs = pv_chain();
+        pvs[1].start_pc = 0x20_0904;
+        assert_eq!(
+            verify_pv_chain(&pvs),
+            Err(ChainError::PcDiscontinuity { index: 1, expected: 0x20_0900, actual: 0x20_0904 })
+        );
+
+        let mut pvs = pv_chain();
+        pvs[3].previous_init_addr_bits[0] = 1;
+        assert_eq!(verify_pv_chain(&pvs), Err(ChainError::InitAddrBits { index: 3 }));
+
+        let mut pvs = pv_chain();
+        pvs[0].previous_finalize_addr_bits[5] = 1;
+        assert_eq!(verify_pv_chain(&pvs), Err(ChainError::FinalizeAddrBits { index: 0 }));
+    }
 }
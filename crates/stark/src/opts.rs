@@ -99,6 +99,10 @@ pub struct SplitOpts {
     pub sha_compress: usize,
     /// The threshold for memory events.
     pub memory: usize,
+    /// Whether to pack memory initialize and finalize events together into shards of at most
+    /// `memory` events, instead of pairing up chunks of each.
+    #[serde(default)]
+    pub pack_memory: bool,
 }
 
 impl SplitOpts {
@@ -111,8 +115,17 @@ impl SplitOpts {
             sha_extend: deferred_shift_threshold / 48,
             sha_compress: deferred_shift_threshold / 80,
             memory: deferred_shift_threshold * 4,
+            pack_memory: false,
         }
     }
+
+    /// Set whether to pack memory initialize and finalize events together into shards of at most
+    /// `memory` events, instead of pairing up chunks of each.
+    #[must_use]
+    pub fn with_pack_memory(mut self, pack_memory: bool) -> Self {
+        self.pack_memory = pack_memory;
+        self
+    }
 }
 
 /// The threshold for splitting deferred events.